@@ -22,8 +22,11 @@ impl<'a> BitVectorIterator<'a> {
         self.pos
     }
 
+    /// move to position pos, discarding the buffered bits
     pub fn at(&mut self, pos: usize) {
         self.pos = pos;
+        self.buf = 0;
+        self.avail = 0;
     }
 
     fn fill_buf(&mut self) {
@@ -44,6 +47,19 @@ impl<'a> BitVectorIterator<'a> {
         val
     }
 
+    /// return 4 bits assuming position is aligned to a 4-bit boundary
+    pub fn take_one_nibble(&mut self) -> u64 {
+        debug_assert!(self.pos % 4 == 0);
+        if self.avail < 4 {
+            self.fill_buf();
+        }
+        let val = self.buf & 15;
+        self.buf >>= 4;
+        self.avail -= 4;
+        self.pos += 4;
+        val
+    }
+
     /// return the next l bits from the current position and advance by l bits
     pub fn take(&mut self, l: usize) -> u64 {
         debug_assert!(l <= 64);
@@ -54,6 +70,8 @@ impl<'a> BitVectorIterator<'a> {
         if l != 64 {
             val &= (1u64 << l).wrapping_sub(1);
             self.buf >>= l;
+        } else {
+            self.buf = 0;
         }
         self.avail -= l;
         self.pos += l;
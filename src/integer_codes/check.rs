@@ -1,55 +1,87 @@
 use super::bit_vector_iterator::BitVectorIterator;
-use super::IntegerCode;
-use crate::introduction::bit_vector::BitVector;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use super::compress::ListsReader;
+use super::compressed_lists::CompressedLists;
+use super::ListCode;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
 use std::path::Path;
 
-pub fn check<C: IntegerCode<u64>, P: AsRef<Path>>(
-    compressed_filename: P,
-    uncompressed_filename: P,
-) {
-    let mut bits = BitVector::new();
-    let file = File::open(compressed_filename).expect("Failed to open compressed file");
-    let reader = BufReader::new(file);
-    bits.load(reader);
-
-    let mut it = BitVectorIterator::new(&bits, 0);
+pub fn check<C: ListCode, P: AsRef<Path>>(compressed_filename: P, uncompressed_filename: P) {
+    let lists = CompressedLists::open(compressed_filename);
+    let mut it = BitVectorIterator::new(lists.bits(), 0);
     let num_lists = it.take(32);
 
-    let file = File::open(uncompressed_filename).expect("Failed to open input file");
-    let reader = BufReader::new(file);
     println!("checking {} lists...", num_lists);
-    let mut list_size: u64 = 0;
-    let mut expected: u64 = 0;
-    let mut prev_x: u64 = 0;
-    let mut x: u64 = 0;
-
-    reader.lines().for_each(|line| {
-        if list_size == 0 {
-            list_size = it.take(32);
-            expected = line.unwrap().parse().unwrap();
-            if list_size != expected {
-                panic!("expected list_size {} but got {}", expected, list_size);
-            }
-            prev_x = 0;
-            x = 0;
-        } else {
-            x = C::read(&mut it) + prev_x;
-            debug_assert!(x >= prev_x);
-            expected = line.unwrap().parse().unwrap();
+    let mut list: Vec<u64> = Vec::new();
+
+    for expected_list in ListsReader::open(uncompressed_filename) {
+        list.clear();
+        C::read_list(&mut it, &mut list);
+        if list.len() != expected_list.len() {
+            panic!(
+                "expected list_size {} but got {}",
+                expected_list.len(),
+                list.len()
+            );
+        }
+        for (&x, &expected) in list.iter().zip(&expected_list) {
             if x != expected {
                 panic!("expected {} but got {}", expected, x);
             }
-            prev_x = x;
-            list_size -= 1;
         }
-    });
+    }
+}
+
+/// check num_samples random lists, each one decoded on its own with the offset directory
+pub fn check_sampled<C: ListCode, P: AsRef<Path>>(
+    compressed_filename: P,
+    uncompressed_filename: P,
+    num_samples: usize,
+) {
+    let lists = CompressedLists::open(compressed_filename);
+    let expected_lists: Vec<Vec<u64>> = ListsReader::open(uncompressed_filename).collect();
+    if lists.num_lists() != expected_lists.len() {
+        panic!(
+            "expected {} lists but got {}",
+            expected_lists.len(),
+            lists.num_lists()
+        );
+    }
+    if lists.num_lists() == 0 {
+        println!("no lists to check");
+        return;
+    }
+
+    println!("checking {} sampled lists...", num_samples);
+    let mut rng = SmallRng::seed_from_u64(42);
+    let mut list: Vec<u64> = Vec::new();
+    for _ in 0..num_samples {
+        let k = rng.gen_range(0..lists.num_lists());
+        list.clear();
+        lists.decode_list::<C>(k, &mut list);
+        if list != expected_lists[k] {
+            panic!("list-{} differs from the uncompressed one", k);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::super::{DeltaCode, GammaCode, RiceCode, VByteCode};
+    use super::super::compress::compress;
+    use super::super::decompress::decompress_sampled;
+    use super::super::elias_fano::EliasFano;
+    use super::super::frame_of_reference::{
+        FrameOfReference128, FrameOfReference32, FrameOfReference64,
+    };
+    use super::super::interpolative::InterpolativeCode;
+    use super::super::partitioned_elias_fano::PartitionedEliasFano;
+    use super::super::pfor::{NewPfdCode, OptPfdCode, PForCode};
+    use super::super::simple::{Simple16Code, Simple8bCode, Simple9Code};
+    use super::super::varint::{GroupVarintCode, StreamVByteCode};
+    use super::super::{
+        AdaptiveRiceCode, DeltaCode, ExpGolombCode, FibonacciCode, GammaCode, GolombCode,
+        LevenshteinCode, NibbleCode, OmegaCode, RiceCode, UnsortedListCode, VByteCode, ZetaCode,
+    };
     use super::*;
 
     #[test]
@@ -67,6 +99,41 @@ mod tests {
         check::<VByteCode, _>("data/out_vbyte.bin", "data/lists.txt");
     }
 
+    #[test]
+    fn check_group_varint() {
+        check::<GroupVarintCode, _>("data/out_group_varint.bin", "data/lists.txt");
+    }
+
+    #[test]
+    fn check_stream_vbyte() {
+        check::<StreamVByteCode, _>("data/out_stream_vbyte.bin", "data/lists.txt");
+    }
+
+    #[test]
+    fn check_omega() {
+        check::<OmegaCode, _>("data/out_omega.bin", "data/lists.txt");
+    }
+
+    #[test]
+    fn check_levenshtein() {
+        check::<LevenshteinCode, _>("data/out_levenshtein.bin", "data/lists.txt");
+    }
+
+    #[test]
+    fn check_fibonacci() {
+        check::<FibonacciCode, _>("data/out_fibonacci.bin", "data/lists.txt");
+    }
+
+    #[test]
+    fn check_nibble() {
+        check::<NibbleCode, _>("data/out_nibble.bin", "data/lists.txt");
+    }
+
+    #[test]
+    fn check_exp_golomb_k2() {
+        check::<ExpGolombCode<2>, _>("data/out_exp_golomb_k2.bin", "data/lists.txt");
+    }
+
     #[test]
     fn check_rice_k1() {
         check::<RiceCode<1>, _>("data/out_rice_k1.bin", "data/lists.txt");
@@ -76,4 +143,115 @@ mod tests {
     fn check_rice_k2() {
         check::<RiceCode<2>, _>("data/out_rice_k2.bin", "data/lists.txt");
     }
+
+    #[test]
+    fn check_rice_adaptive() {
+        check::<AdaptiveRiceCode, _>("data/out_rice_adaptive.bin", "data/lists.txt");
+    }
+
+    #[test]
+    fn check_golomb_b3() {
+        check::<GolombCode<3>, _>("data/out_golomb_b3.bin", "data/lists.txt");
+    }
+
+    #[test]
+    fn check_zeta_k2() {
+        check::<ZetaCode<2>, _>("data/out_zeta_k2.bin", "data/lists.txt");
+    }
+
+    #[test]
+    fn check_zeta_k3() {
+        check::<ZetaCode<3>, _>("data/out_zeta_k3.bin", "data/lists.txt");
+    }
+
+    #[test]
+    fn check_ef() {
+        check::<EliasFano, _>("data/out_ef.bin", "data/lists.txt");
+    }
+
+    #[test]
+    fn check_pef() {
+        check::<PartitionedEliasFano, _>("data/out_pef.bin", "data/lists.txt");
+    }
+
+    #[test]
+    fn check_bic() {
+        check::<InterpolativeCode, _>("data/out_bic.bin", "data/lists.txt");
+    }
+
+    #[test]
+    fn check_unsorted_delta() {
+        check::<UnsortedListCode<DeltaCode>, _>("data/out_unsorted_delta.bin", "data/lists.txt");
+    }
+
+    #[test]
+    fn check_pfor() {
+        check::<PForCode, _>("data/out_pfor.bin", "data/lists.txt");
+    }
+
+    #[test]
+    fn check_newpfd() {
+        check::<NewPfdCode, _>("data/out_newpfd.bin", "data/lists.txt");
+    }
+
+    #[test]
+    fn check_optpfd() {
+        check::<OptPfdCode, _>("data/out_optpfd.bin", "data/lists.txt");
+    }
+
+    #[test]
+    fn check_simple9() {
+        check::<Simple9Code, _>("data/out_simple9.bin", "data/lists.txt");
+    }
+
+    #[test]
+    fn check_simple16() {
+        check::<Simple16Code, _>("data/out_simple16.bin", "data/lists.txt");
+    }
+
+    #[test]
+    fn check_simple8b() {
+        check::<Simple8bCode, _>("data/out_simple8b.bin", "data/lists.txt");
+    }
+
+    #[test]
+    fn check_for32() {
+        check::<FrameOfReference32, _>("data/out_for32.bin", "data/lists.txt");
+    }
+
+    #[test]
+    fn check_for64() {
+        check::<FrameOfReference64, _>("data/out_for64.bin", "data/lists.txt");
+    }
+
+    #[test]
+    fn check_for128() {
+        check::<FrameOfReference128, _>("data/out_for128.bin", "data/lists.txt");
+    }
+
+    #[test]
+    fn check_sampled_gamma() {
+        check_sampled::<GammaCode, _>("data/out_gamma.bin", "data/lists.txt", 100);
+    }
+
+    #[test]
+    fn check_sampled_pef() {
+        check_sampled::<PartitionedEliasFano, _>("data/out_pef.bin", "data/lists.txt", 100);
+    }
+
+    #[test]
+    fn check_sampled_no_lists() {
+        std::fs::write("data/lists_empty.txt", "").unwrap();
+        compress::<GammaCode, _>("data/lists_empty.txt", "data/out_empty.bin");
+        check::<GammaCode, _>("data/out_empty.bin", "data/lists_empty.txt");
+        check_sampled::<GammaCode, _>("data/out_empty.bin", "data/lists_empty.txt", 100);
+        decompress_sampled::<GammaCode, _>("data/out_empty.bin", 100);
+    }
+
+    #[test]
+    fn check_empty_lists() {
+        std::fs::write("data/lists_with_empty.txt", "0\n2\n1\n5\n0\n").unwrap();
+        compress::<GammaCode, _>("data/lists_with_empty.txt", "data/out_with_empty.bin");
+        check::<GammaCode, _>("data/out_with_empty.bin", "data/lists_with_empty.txt");
+    }
 }
@@ -1,61 +1,151 @@
-use super::IntegerCode;
-use crate::introduction::bit_vector::{BitVector, BitVectorBuilder};
+use super::compressed_lists::CompressedLists;
+use super::{gaps, optimal_rice_parameter, rice_cost, ListCode};
+use crate::introduction::bit_vector::BitVectorBuilder;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Lines};
 use std::path::Path;
 
-pub fn compress<C: IntegerCode<u64>, P: AsRef<Path>>(input_lists_filename: P, output_filename: P) {
+/// iterate over the lists of a text file where each list is given by its size
+/// followed by its values, one integer per line
+pub struct ListsReader {
+    lines: Lines<BufReader<File>>,
+}
+
+impl ListsReader {
+    pub fn open<P: AsRef<Path>>(filename: P) -> Self {
+        let file = File::open(filename).expect("Failed to open input file");
+        Self {
+            lines: BufReader::new(file).lines(),
+        }
+    }
+
+    fn next_int(&mut self) -> Option<u64> {
+        self.lines.next().map(|line| line.unwrap().parse().unwrap())
+    }
+}
+
+impl Iterator for ListsReader {
+    type Item = Vec<u64>;
+
+    fn next(&mut self) -> Option<Vec<u64>> {
+        let list_size = self.next_int()?;
+        let list = (0..list_size)
+            .map(|_| self.next_int().expect("truncated list"))
+            .collect();
+        Some(list)
+    }
+}
+
+/// bits per item, 0 when there are no items
+fn bits_per(bits: usize, n: usize) -> f64 {
+    if n == 0 {
+        0.0
+    } else {
+        bits as f64 / n as f64
+    }
+}
+
+pub fn compress<C: ListCode, P: AsRef<Path>>(input_lists_filename: P, output_filename: P) {
     let mut builder = BitVectorBuilder::new();
     builder.append_bits(0, 32); // reserve the first 32-bit int for num_lists
 
     let mut num_ints: usize = 0;
     let mut num_lists: u64 = 0;
-    let mut list_size: u64 = 0;
-    let mut prev_x: u64 = 0;
-    let mut x: u64 = 0;
-
-    let file = File::open(input_lists_filename).expect("Failed to open input file");
-    let reader = BufReader::new(file);
-    reader.lines().for_each(|line| {
-        if list_size == 0 {
-            list_size = line.unwrap().parse().unwrap();
-            println!("list-{} size = {}", num_lists, list_size);
-            builder.append_bits(list_size, 32);
-            num_lists += 1;
-            prev_x = 0;
-            x = 0;
-        } else {
-            x = line.unwrap().parse().unwrap();
-            debug_assert!(x >= prev_x);
-            C::write(&mut builder, x - prev_x);
-            num_ints += 1;
-            list_size -= 1;
-            prev_x = x;
-        }
-    });
+    let mut list_starts: Vec<u64> = Vec::new();
+
+    for list in ListsReader::open(input_lists_filename) {
+        let list_start = builder.num_bits();
+        list_starts.push(list_start as u64);
+        C::write_list(&mut builder, &list);
+        println!(
+            "list-{} size = {} ({:.2} bits/int)",
+            num_lists,
+            list.len(),
+            bits_per(builder.num_bits() - list_start, list.len())
+        );
+        num_ints += list.len();
+        num_lists += 1;
+    }
 
     builder.set_bits(0, num_lists, 32);
 
     println!("compressed {} lists", num_lists);
     println!("({} integers)", num_ints);
     println!("written {} bits", builder.num_bits());
+    println!("({:.2} bits/int)", bits_per(builder.num_bits(), num_ints));
+
+    let mut lists = CompressedLists::new();
+    lists.build(&mut builder, &list_starts);
     println!(
-        "({:.2} bits/int)",
-        builder.num_bits() as f64 / num_ints as f64
+        "written {} bits for the list offsets ({:.2} bits/list)",
+        lists.directory_bits(),
+        bits_per(lists.directory_bits(), num_lists as usize)
     );
 
-    let mut bits = BitVector::new();
-    builder.build(&mut bits);
-
     let file = File::create(output_filename).expect("Failed to open output file");
-    bits.save(file);
+    lists.save(file);
+}
+
+/// space of the lists in Rice code with the best k for each list, and with fixed values of k
+pub struct RiceSavings {
+    pub num_lists: usize,
+    /// including the 6 bits of the parameter of each list
+    pub adaptive_bits: usize,
+    /// fixed_bits[k-1] is the space with the parameter k for all lists
+    pub fixed_bits: Vec<usize>,
+}
+
+/// report the bits saved by `AdaptiveRiceCode` over `RiceCode<K>` for K = 1..=max_k,
+/// only counting the gaps and the parameters, without the list sizes
+pub fn rice_savings<P: AsRef<Path>>(input_lists_filename: P, max_k: usize) -> RiceSavings {
+    let mut savings = RiceSavings {
+        num_lists: 0,
+        adaptive_bits: 0,
+        fixed_bits: vec![0; max_k],
+    };
+    for list in ListsReader::open(input_lists_filename) {
+        let gaps = gaps(&list);
+        savings.num_lists += 1;
+        savings.adaptive_bits += rice_cost(&gaps, optimal_rice_parameter(&gaps)) + 6;
+        for (k, bits) in (1..=max_k).zip(&mut savings.fixed_bits) {
+            *bits += rice_cost(&gaps, k);
+        }
+    }
+
+    for (k, &fixed_bits) in (1..=max_k).zip(&savings.fixed_bits) {
+        println!(
+            "adaptive k saves {} bits ({:.2}%) over fixed k={}",
+            fixed_bits as i64 - savings.adaptive_bits as i64,
+            (fixed_bits as f64 - savings.adaptive_bits as f64) * 100.0 / fixed_bits as f64,
+            k
+        );
+    }
+    savings
 }
 
 #[cfg(test)]
 mod tests {
-    use super::super::{DeltaCode, GammaCode, RiceCode, VByteCode};
+    use super::super::elias_fano::EliasFano;
+    use super::super::frame_of_reference::{
+        FrameOfReference128, FrameOfReference32, FrameOfReference64,
+    };
+    use super::super::interpolative::InterpolativeCode;
+    use super::super::partitioned_elias_fano::PartitionedEliasFano;
+    use super::super::pfor::{NewPfdCode, OptPfdCode, PForCode};
+    use super::super::simple::{Simple16Code, Simple8bCode, Simple9Code};
+    use super::super::varint::{GroupVarintCode, StreamVByteCode};
+    use super::super::{
+        AdaptiveRiceCode, DeltaCode, ExpGolombCode, FibonacciCode, GammaCode, GolombCode,
+        LevenshteinCode, NibbleCode, OmegaCode, RiceCode, UnsortedListCode, VByteCode, ZetaCode,
+    };
     use super::*;
 
+    #[test]
+    fn bits_per_no_items() {
+        assert_eq!(bits_per(32, 0), 0.0);
+        assert_eq!(bits_per(32, 4), 8.0);
+    }
+
     #[test]
     fn compress_gamma() {
         compress::<GammaCode, _>("data/lists.txt", "data/out_gamma.bin");
@@ -71,6 +161,41 @@ mod tests {
         compress::<VByteCode, _>("data/lists.txt", "data/out_vbyte.bin");
     }
 
+    #[test]
+    fn compress_group_varint() {
+        compress::<GroupVarintCode, _>("data/lists.txt", "data/out_group_varint.bin");
+    }
+
+    #[test]
+    fn compress_stream_vbyte() {
+        compress::<StreamVByteCode, _>("data/lists.txt", "data/out_stream_vbyte.bin");
+    }
+
+    #[test]
+    fn compress_omega() {
+        compress::<OmegaCode, _>("data/lists.txt", "data/out_omega.bin");
+    }
+
+    #[test]
+    fn compress_levenshtein() {
+        compress::<LevenshteinCode, _>("data/lists.txt", "data/out_levenshtein.bin");
+    }
+
+    #[test]
+    fn compress_fibonacci() {
+        compress::<FibonacciCode, _>("data/lists.txt", "data/out_fibonacci.bin");
+    }
+
+    #[test]
+    fn compress_nibble() {
+        compress::<NibbleCode, _>("data/lists.txt", "data/out_nibble.bin");
+    }
+
+    #[test]
+    fn compress_exp_golomb_k2() {
+        compress::<ExpGolombCode<2>, _>("data/lists.txt", "data/out_exp_golomb_k2.bin");
+    }
+
     #[test]
     fn compress_rice_k1() {
         compress::<RiceCode<1>, _>("data/lists.txt", "data/out_rice_k1.bin");
@@ -80,4 +205,98 @@ mod tests {
     fn compress_rice_k2() {
         compress::<RiceCode<2>, _>("data/lists.txt", "data/out_rice_k2.bin");
     }
+
+    #[test]
+    fn compress_rice_adaptive() {
+        compress::<AdaptiveRiceCode, _>("data/lists.txt", "data/out_rice_adaptive.bin");
+    }
+
+    #[test]
+    fn rice_adaptive_savings() {
+        let savings = rice_savings("data/lists.txt", 4);
+        // without the parameters, the best k of each list cannot be worse than a fixed one
+        for &fixed_bits in &savings.fixed_bits {
+            assert!(savings.adaptive_bits - 6 * savings.num_lists <= fixed_bits);
+        }
+    }
+
+    #[test]
+    fn compress_golomb_b3() {
+        compress::<GolombCode<3>, _>("data/lists.txt", "data/out_golomb_b3.bin");
+    }
+
+    #[test]
+    fn compress_zeta_k2() {
+        compress::<ZetaCode<2>, _>("data/lists.txt", "data/out_zeta_k2.bin");
+    }
+
+    #[test]
+    fn compress_zeta_k3() {
+        compress::<ZetaCode<3>, _>("data/lists.txt", "data/out_zeta_k3.bin");
+    }
+
+    #[test]
+    fn compress_ef() {
+        compress::<EliasFano, _>("data/lists.txt", "data/out_ef.bin");
+    }
+
+    #[test]
+    fn compress_pef() {
+        compress::<PartitionedEliasFano, _>("data/lists.txt", "data/out_pef.bin");
+    }
+
+    #[test]
+    fn compress_bic() {
+        compress::<InterpolativeCode, _>("data/lists.txt", "data/out_bic.bin");
+    }
+
+    #[test]
+    fn compress_unsorted_delta() {
+        compress::<UnsortedListCode<DeltaCode>, _>("data/lists.txt", "data/out_unsorted_delta.bin");
+    }
+
+    #[test]
+    fn compress_pfor() {
+        compress::<PForCode, _>("data/lists.txt", "data/out_pfor.bin");
+    }
+
+    #[test]
+    fn compress_newpfd() {
+        compress::<NewPfdCode, _>("data/lists.txt", "data/out_newpfd.bin");
+    }
+
+    #[test]
+    fn compress_optpfd() {
+        compress::<OptPfdCode, _>("data/lists.txt", "data/out_optpfd.bin");
+    }
+
+    #[test]
+    fn compress_simple9() {
+        compress::<Simple9Code, _>("data/lists.txt", "data/out_simple9.bin");
+    }
+
+    #[test]
+    fn compress_simple16() {
+        compress::<Simple16Code, _>("data/lists.txt", "data/out_simple16.bin");
+    }
+
+    #[test]
+    fn compress_simple8b() {
+        compress::<Simple8bCode, _>("data/lists.txt", "data/out_simple8b.bin");
+    }
+
+    #[test]
+    fn compress_for32() {
+        compress::<FrameOfReference32, _>("data/lists.txt", "data/out_for32.bin");
+    }
+
+    #[test]
+    fn compress_for64() {
+        compress::<FrameOfReference64, _>("data/lists.txt", "data/out_for64.bin");
+    }
+
+    #[test]
+    fn compress_for128() {
+        compress::<FrameOfReference128, _>("data/lists.txt", "data/out_for128.bin");
+    }
 }
@@ -0,0 +1,129 @@
+use super::bit_vector_iterator::BitVectorIterator;
+use super::elias_fano::EliasFano;
+use super::ListCode;
+use crate::introduction::bit_vector::{BitVector, BitVectorBuilder};
+use bincode::{deserialize_from, serialize_into};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
+use std::path::Path;
+
+/// The compressed lists: the bitstream written by `compress`, i.e., the 32-bit number of lists
+/// followed by the lists, and an Elias-Fano directory of the bit positions where each list
+/// starts, so that any list can be decoded without decoding the previous ones.
+#[derive(Default, Serialize, Deserialize)]
+pub struct CompressedLists {
+    bits: BitVector,
+    offsets: EliasFano,
+}
+
+impl CompressedLists {
+    pub fn new() -> Self {
+        Self {
+            bits: BitVector::new(),
+            offsets: EliasFano::new(),
+        }
+    }
+
+    /// take the bits of the builder, in which the k-th list starts at list_starts[k]
+    pub fn build(&mut self, builder: &mut BitVectorBuilder, list_starts: &[u64]) {
+        builder.build(&mut self.bits);
+        self.offsets.build(list_starts);
+    }
+
+    pub fn num_lists(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub fn bits(&self) -> &BitVector {
+        &self.bits
+    }
+
+    /// number of bits taken by the offset directory
+    pub fn directory_bits(&self) -> usize {
+        self.offsets.num_bits()
+    }
+
+    /// decode the k-th list and append it to out
+    pub fn decode_list<C: ListCode>(&self, k: usize, out: &mut Vec<u64>) {
+        debug_assert!(k < self.num_lists());
+        let mut it = BitVectorIterator::new(&self.bits, self.offsets.access(k) as usize);
+        C::read_list(&mut it, out);
+    }
+
+    pub fn open<P: AsRef<Path>>(filename: P) -> Self {
+        let file = File::open(filename).expect("Failed to open compressed file");
+        let mut lists = Self::new();
+        lists.load(BufReader::new(file));
+        lists
+    }
+
+    pub fn save<W: Write>(&self, writer: W) {
+        serialize_into(writer, &self).unwrap();
+    }
+
+    pub fn load<R: Read>(&mut self, reader: R) {
+        *self = deserialize_from(reader).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_utils::random_list;
+    use super::super::{DeltaCode, VByteCode};
+    use super::*;
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn decode_list() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let lists: Vec<Vec<u64>> = (0..500)
+            .map(|seed| random_list(rng.gen_range(0..100), 1..1000, seed))
+            .collect();
+
+        let mut builder = BitVectorBuilder::new();
+        builder.append_bits(lists.len() as u64, 32);
+        let mut list_starts = Vec::new();
+        for list in &lists {
+            list_starts.push(builder.num_bits() as u64);
+            DeltaCode::write_list(&mut builder, list);
+        }
+        let mut compressed = CompressedLists::new();
+        compressed.build(&mut builder, &list_starts);
+
+        let mut buf = Vec::new();
+        compressed.save(&mut buf);
+        let mut loaded = CompressedLists::new();
+        loaded.load(&buf[..]);
+        assert_eq!(loaded.num_lists(), lists.len());
+        for _ in 0..1000 {
+            let k = rng.gen_range(0..lists.len());
+            let mut list = Vec::new();
+            loaded.decode_list::<DeltaCode>(k, &mut list);
+            assert_eq!(list, lists[k]);
+        }
+
+        // the lists can still be decoded sequentially
+        let mut it = BitVectorIterator::new(loaded.bits(), 0);
+        assert_eq!(it.take(32) as usize, lists.len());
+        for expected in &lists {
+            let mut list = Vec::new();
+            DeltaCode::read_list(&mut it, &mut list);
+            assert_eq!(list, *expected);
+        }
+
+        // with another code, the directory points to other positions
+        let mut builder = BitVectorBuilder::new();
+        builder.append_bits(lists.len() as u64, 32);
+        let mut list_starts = Vec::new();
+        for list in &lists {
+            list_starts.push(builder.num_bits() as u64);
+            VByteCode::write_list(&mut builder, list);
+        }
+        compressed.build(&mut builder, &list_starts);
+        let mut list = Vec::new();
+        compressed.decode_list::<VByteCode>(lists.len() - 1, &mut list);
+        assert_eq!(list, *lists.last().unwrap());
+    }
+}
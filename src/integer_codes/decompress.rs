@@ -1,36 +1,62 @@
-use super::IntegerCode;
+use super::compressed_lists::CompressedLists;
+use super::ListCode;
 use crate::integer_codes::bit_vector_iterator::BitVectorIterator;
-use crate::introduction::bit_vector::BitVector;
-use std::fs::File;
-use std::io::BufReader;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
 use std::path::Path;
 use std::time::Instant;
 
-pub fn decompress<C: IntegerCode<u64>, P: AsRef<Path>>(input_filename: P) {
-    let mut bits = BitVector::new();
-    let file = File::open(input_filename).expect("Failed to open input file");
-    let reader = BufReader::new(file);
-    bits.load(reader);
+/// decompress all the lists and return the decoding time in ns per integer
+pub fn decompress<C: ListCode, P: AsRef<Path>>(input_filename: P) -> f64 {
+    let lists = CompressedLists::open(input_filename);
 
-    println!("loaded {} bits", bits.num_bits());
+    println!("loaded {} bits", lists.bits().num_bits());
 
-    let mut it = BitVectorIterator::new(&bits, 0);
+    let mut it = BitVectorIterator::new(lists.bits(), 0);
     let num_lists = it.take(32);
     let mut num_ints = 0;
+    let mut list: Vec<u64> = Vec::new();
 
     println!("decompressing {} lists...", num_lists);
     let now = Instant::now();
 
     for _ in 0..num_lists {
-        let list_size = it.take(32);
-        let mut prev_x = 0;
-        let mut x;
-        for _ in 0..list_size {
-            x = C::read(&mut it) + prev_x;
-            debug_assert!(x >= prev_x);
-            prev_x = x;
-        }
-        num_ints += list_size;
+        list.clear();
+        C::read_list(&mut it, &mut list);
+        num_ints += list.len();
+    }
+
+    let elapsed = now.elapsed().as_micros();
+    let ns_per_int = elapsed as f64 * 1000.0 / num_ints as f64;
+    println!("decompressed {} integers in {} µs", num_ints, elapsed);
+    println!("({:.2} ns/int)", ns_per_int);
+    ns_per_int
+}
+
+/// decompress num_samples random lists, each one on its own with the offset directory
+pub fn decompress_sampled<C: ListCode, P: AsRef<Path>>(input_filename: P, num_samples: usize) {
+    let lists = CompressedLists::open(input_filename);
+
+    println!("loaded {} bits", lists.bits().num_bits());
+    if lists.num_lists() == 0 {
+        println!("no lists to decompress");
+        return;
+    }
+
+    let mut rng = SmallRng::seed_from_u64(42);
+    let samples: Vec<usize> = (0..num_samples)
+        .map(|_| rng.gen_range(0..lists.num_lists()))
+        .collect();
+    let mut num_ints = 0;
+    let mut list: Vec<u64> = Vec::new();
+
+    println!("decompressing {} sampled lists...", num_samples);
+    let now = Instant::now();
+
+    for &k in &samples {
+        list.clear();
+        lists.decode_list::<C>(k, &mut list);
+        num_ints += list.len();
     }
 
     let elapsed = now.elapsed().as_micros();
@@ -40,7 +66,19 @@ pub fn decompress<C: IntegerCode<u64>, P: AsRef<Path>>(input_filename: P) {
 
 #[cfg(test)]
 mod tests {
-    use super::super::{DeltaCode, GammaCode, RiceCode, VByteCode};
+    use super::super::elias_fano::EliasFano;
+    use super::super::frame_of_reference::{
+        FrameOfReference128, FrameOfReference32, FrameOfReference64,
+    };
+    use super::super::interpolative::InterpolativeCode;
+    use super::super::partitioned_elias_fano::PartitionedEliasFano;
+    use super::super::pfor::{NewPfdCode, OptPfdCode, PForCode};
+    use super::super::simple::{Simple16Code, Simple8bCode, Simple9Code};
+    use super::super::varint::{GroupVarintCode, StreamVByteCode};
+    use super::super::{
+        AdaptiveRiceCode, DeltaCode, ExpGolombCode, FibonacciCode, GammaCode, GolombCode,
+        LevenshteinCode, NibbleCode, OmegaCode, RiceCode, UnsortedListCode, VByteCode, ZetaCode,
+    };
     use super::*;
 
     #[test]
@@ -58,6 +96,52 @@ mod tests {
         decompress::<VByteCode, _>("data/out_vbyte.bin");
     }
 
+    #[test]
+    fn decompress_group_varint() {
+        decompress::<GroupVarintCode, _>("data/out_group_varint.bin");
+    }
+
+    #[test]
+    fn decompress_stream_vbyte() {
+        decompress::<StreamVByteCode, _>("data/out_stream_vbyte.bin");
+    }
+
+    #[test]
+    fn decompress_byte_aligned() {
+        let vbyte = decompress::<VByteCode, _>("data/out_vbyte.bin");
+        let group_varint = decompress::<GroupVarintCode, _>("data/out_group_varint.bin");
+        let stream_vbyte = decompress::<StreamVByteCode, _>("data/out_stream_vbyte.bin");
+        println!(
+            "vbyte: {:.2} ns/int, group varint: {:.2} ns/int, stream vbyte: {:.2} ns/int",
+            vbyte, group_varint, stream_vbyte
+        );
+    }
+
+    #[test]
+    fn decompress_omega() {
+        decompress::<OmegaCode, _>("data/out_omega.bin");
+    }
+
+    #[test]
+    fn decompress_levenshtein() {
+        decompress::<LevenshteinCode, _>("data/out_levenshtein.bin");
+    }
+
+    #[test]
+    fn decompress_fibonacci() {
+        decompress::<FibonacciCode, _>("data/out_fibonacci.bin");
+    }
+
+    #[test]
+    fn decompress_nibble() {
+        decompress::<NibbleCode, _>("data/out_nibble.bin");
+    }
+
+    #[test]
+    fn decompress_exp_golomb_k2() {
+        decompress::<ExpGolombCode<2>, _>("data/out_exp_golomb_k2.bin");
+    }
+
     #[test]
     fn decompress_rice_k1() {
         decompress::<RiceCode<1>, _>("data/out_rice_k1.bin");
@@ -67,4 +151,99 @@ mod tests {
     fn decompress_rice_k2() {
         decompress::<RiceCode<2>, _>("data/out_rice_k2.bin");
     }
+
+    #[test]
+    fn decompress_rice_adaptive() {
+        decompress::<AdaptiveRiceCode, _>("data/out_rice_adaptive.bin");
+    }
+
+    #[test]
+    fn decompress_golomb_b3() {
+        decompress::<GolombCode<3>, _>("data/out_golomb_b3.bin");
+    }
+
+    #[test]
+    fn decompress_zeta_k2() {
+        decompress::<ZetaCode<2>, _>("data/out_zeta_k2.bin");
+    }
+
+    #[test]
+    fn decompress_zeta_k3() {
+        decompress::<ZetaCode<3>, _>("data/out_zeta_k3.bin");
+    }
+
+    #[test]
+    fn decompress_ef() {
+        decompress::<EliasFano, _>("data/out_ef.bin");
+    }
+
+    #[test]
+    fn decompress_pef() {
+        decompress::<PartitionedEliasFano, _>("data/out_pef.bin");
+    }
+
+    #[test]
+    fn decompress_bic() {
+        decompress::<InterpolativeCode, _>("data/out_bic.bin");
+    }
+
+    #[test]
+    fn decompress_unsorted_delta() {
+        decompress::<UnsortedListCode<DeltaCode>, _>("data/out_unsorted_delta.bin");
+    }
+
+    #[test]
+    fn decompress_pfor() {
+        decompress::<PForCode, _>("data/out_pfor.bin");
+    }
+
+    #[test]
+    fn decompress_newpfd() {
+        decompress::<NewPfdCode, _>("data/out_newpfd.bin");
+    }
+
+    #[test]
+    fn decompress_optpfd() {
+        decompress::<OptPfdCode, _>("data/out_optpfd.bin");
+    }
+
+    #[test]
+    fn decompress_simple9() {
+        decompress::<Simple9Code, _>("data/out_simple9.bin");
+    }
+
+    #[test]
+    fn decompress_simple16() {
+        decompress::<Simple16Code, _>("data/out_simple16.bin");
+    }
+
+    #[test]
+    fn decompress_simple8b() {
+        decompress::<Simple8bCode, _>("data/out_simple8b.bin");
+    }
+
+    #[test]
+    fn decompress_for32() {
+        decompress::<FrameOfReference32, _>("data/out_for32.bin");
+    }
+
+    #[test]
+    fn decompress_for64() {
+        decompress::<FrameOfReference64, _>("data/out_for64.bin");
+    }
+
+    #[test]
+    fn decompress_for128() {
+        decompress::<FrameOfReference128, _>("data/out_for128.bin");
+    }
+
+    #[test]
+    fn decompress_sampled_gamma() {
+        decompress_sampled::<GammaCode, _>("data/out_gamma.bin", 1000);
+    }
+
+    #[test]
+    fn decompress_sampled_vbyte() {
+        decompress_sampled::<VByteCode, _>("data/out_vbyte.bin", 1000);
+    }
 }
@@ -0,0 +1,296 @@
+use super::bit_vector_iterator::BitVectorIterator;
+use super::{DeltaCode, IntegerCode, ListCode, UnaryCode};
+use crate::introduction::bit_vector::{BitVector, BitVectorBuilder};
+use crate::introduction::util::msb;
+use crate::succinct::darray::{DArray0, DArray1};
+use bincode::{deserialize_from, serialize_into};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+
+/// number of low bits l = floor(log2(u/n)) for n sorted values smaller than u
+pub(super) fn num_low_bits(n: usize, last: u64) -> usize {
+    let universe = last.saturating_add(1);
+    if n == 0 || universe <= n as u64 {
+        0
+    } else {
+        msb(universe / n as u64)
+    }
+}
+
+pub(super) fn low_mask(l: usize) -> u64 {
+    (1u64 << l).wrapping_sub(1)
+}
+
+/// Elias-Fano representation of a sorted sequence of integers.
+///
+/// Each value is split into its l low bits, stored verbatim in `lower`,
+/// and its high part, stored in `upper` by setting the bit (v >> l) + i for the i-th value.
+/// Select directories over `upper` give random access and next_geq in O(1).
+#[derive(Default, Serialize, Deserialize)]
+pub struct EliasFano {
+    num_values: usize,
+    low_bits: usize,
+    lower: BitVector,
+    upper: BitVector,
+    select1: DArray1,
+    select0: DArray0,
+}
+
+impl EliasFano {
+    pub fn new() -> Self {
+        Self {
+            num_values: 0,
+            low_bits: 0,
+            lower: BitVector::new(),
+            upper: BitVector::new(),
+            select1: DArray1::new(),
+            select0: DArray0::new(),
+        }
+    }
+
+    pub fn build(&mut self, values: &[u64]) {
+        let n = values.len();
+        let last = values.last().copied().unwrap_or(0);
+        let l = num_low_bits(n, last);
+
+        let mut lower = BitVectorBuilder::new();
+        lower.reserve(n * l);
+        let mut upper = BitVectorBuilder::new();
+        upper.resize(if n == 0 { 0 } else { n + (last >> l) as usize });
+
+        let mut prev = 0;
+        for (i, &v) in values.iter().enumerate() {
+            debug_assert!(v >= prev);
+            lower.append_bits(v & low_mask(l), l);
+            upper.set_bits((v >> l) as usize + i, 1, 1);
+            prev = v;
+        }
+
+        self.num_values = n;
+        self.low_bits = l;
+        lower.build(&mut self.lower);
+        upper.build(&mut self.upper);
+        self.select1.build(&self.upper);
+        self.select0.build(&self.upper);
+    }
+
+    pub fn len(&self) -> usize {
+        self.num_values
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.num_values == 0
+    }
+
+    /// total number of bits, including the select directories
+    pub fn num_bits(&self) -> usize {
+        self.lower.num_bits()
+            + self.upper.num_bits()
+            + self.select1.num_bits()
+            + self.select0.num_bits()
+    }
+
+    fn low(&self, i: usize) -> u64 {
+        self.lower.get_bits(i * self.low_bits, self.low_bits)
+    }
+
+    /// return the i-th value
+    pub fn access(&self, i: usize) -> u64 {
+        debug_assert!(i < self.num_values);
+        let high = (self.select1.select(&self.upper, i) - i) as u64;
+        (high << self.low_bits) | self.low(i)
+    }
+
+    /// return the position and value of the first value >= x, if any
+    pub fn next_geq(&self, x: u64) -> Option<(usize, u64)> {
+        let high = (x >> self.low_bits) as usize;
+        // first bit of the bucket of high part `high`, and number of values before it
+        let (i, pos) = if high == 0 {
+            (0, 0)
+        } else if high > self.select0.num_positions() {
+            return None;
+        } else {
+            let pos = self.select0.select(&self.upper, high - 1) + 1;
+            (pos - high, pos)
+        };
+        let mut it = self.iter_from(i, pos, high as u64);
+        let mut i = i;
+        loop {
+            let v = it.next()?;
+            if v >= x {
+                return Some((i, v));
+            }
+            i += 1;
+        }
+    }
+
+    fn iter_from(&self, i: usize, upper_pos: usize, high: u64) -> EliasFanoIterator<'_> {
+        EliasFanoIterator {
+            ef: self,
+            i,
+            high,
+            upper_it: BitVectorIterator::new(&self.upper, upper_pos),
+        }
+    }
+
+    pub fn iter(&self) -> EliasFanoIterator<'_> {
+        self.iter_from(0, 0, 0)
+    }
+
+    /// append the sorted values to the builder: the last value in delta code,
+    /// the low bits of all values, then the gaps between high parts in unary
+    pub fn encode(builder: &mut BitVectorBuilder, values: &[u64]) {
+        let n = values.len();
+        if n == 0 {
+            return;
+        }
+        let last = values[n - 1];
+        let l = num_low_bits(n, last);
+        DeltaCode::write(builder, last);
+        for &v in values {
+            builder.append_bits(v & low_mask(l), l);
+        }
+        let mut prev_high = 0;
+        for &v in values {
+            UnaryCode::write(builder, (v >> l) - prev_high);
+            prev_high = v >> l;
+        }
+    }
+
+    /// decode n values written by `encode` and append them to out
+    pub fn decode(it: &mut BitVectorIterator, n: usize, out: &mut Vec<u64>) {
+        if n == 0 {
+            return;
+        }
+        let last = DeltaCode::read(it);
+        let l = num_low_bits(n, last);
+        let start = out.len();
+        for _ in 0..n {
+            out.push(it.take(l));
+        }
+        let mut high = 0;
+        for low in &mut out[start..] {
+            high += it.skip_zeros() as u64;
+            *low |= high << l;
+        }
+    }
+
+    pub fn save<W: Write>(&self, writer: W) {
+        serialize_into(writer, &self).unwrap();
+    }
+
+    pub fn load<R: Read>(&mut self, reader: R) {
+        *self = deserialize_from(reader).unwrap();
+    }
+}
+
+impl ListCode for EliasFano {
+    /// the 32-bit size of the list followed by `EliasFano::encode`
+    fn write_list(builder: &mut BitVectorBuilder, list: &[u64]) {
+        builder.append_bits(list.len() as u64, 32);
+        Self::encode(builder, list);
+    }
+
+    fn read_list(it: &mut BitVectorIterator, out: &mut Vec<u64>) {
+        let list_size = it.take(32) as usize;
+        Self::decode(it, list_size, out);
+    }
+}
+
+pub struct EliasFanoIterator<'a> {
+    ef: &'a EliasFano,
+    i: usize,
+    high: u64,
+    upper_it: BitVectorIterator<'a>,
+}
+
+impl Iterator for EliasFanoIterator<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.i == self.ef.num_values {
+            return None;
+        }
+        self.high += self.upper_it.skip_zeros() as u64;
+        let v = (self.high << self.ef.low_bits) | self.ef.low(self.i);
+        self.i += 1;
+        Some(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    fn random_sorted(n: usize, universe: u64, seed: u64) -> Vec<u64> {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let mut values: Vec<u64> = (0..n).map(|_| rng.gen_range(0..universe)).collect();
+        values.sort_unstable();
+        values
+    }
+
+    #[test]
+    fn elias_fano() {
+        let lists = [
+            vec![],
+            vec![0],
+            vec![0, 0, 0],
+            vec![5, 1 << 40, u64::MAX - 1],
+            random_sorted(1000, 1000, 1),
+            random_sorted(10_000, 1 << 32, 2),
+            random_sorted(5000, 100, 3),
+        ];
+        for values in &lists {
+            let mut ef = EliasFano::new();
+            ef.build(values);
+            assert_eq!(ef.len(), values.len());
+            assert_eq!(ef.iter().collect::<Vec<_>>(), *values);
+            for (i, &v) in values.iter().enumerate() {
+                assert_eq!(ef.access(i), v);
+            }
+
+            let mut probes: Vec<u64> = values.iter().flat_map(|&v| [v, v + 1]).collect();
+            probes.push(0);
+            for x in probes {
+                let expected = values.iter().position(|&v| v >= x).map(|i| (i, values[i]));
+                assert_eq!(ef.next_geq(x), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn elias_fano_encode_decode() {
+        let values = random_sorted(10_000, 1 << 20, 4);
+        let mut builder = BitVectorBuilder::new();
+        EliasFano::encode(&mut builder, &values);
+        EliasFano::encode(&mut builder, &values[..10]);
+        let mut bv = BitVector::new();
+        builder.build(&mut bv);
+
+        let mut it = BitVectorIterator::new(&bv, 0);
+        let mut decoded = Vec::new();
+        EliasFano::decode(&mut it, values.len(), &mut decoded);
+        EliasFano::decode(&mut it, 10, &mut decoded);
+        assert_eq!(decoded[..values.len()], values);
+        assert_eq!(decoded[values.len()..], values[..10]);
+    }
+
+    #[test]
+    fn elias_fano_save_load() {
+        let values = random_sorted(1000, 1 << 16, 5);
+        let mut ef = EliasFano::new();
+        ef.build(&values);
+        println!(
+            "elias_fano uses {:.2} bits/int",
+            ef.num_bits() as f64 / values.len() as f64
+        );
+
+        let mut buf = Vec::new();
+        ef.save(&mut buf);
+        let mut loaded = EliasFano::new();
+        loaded.load(&buf[..]);
+        assert_eq!(loaded.iter().collect::<Vec<_>>(), values);
+    }
+}
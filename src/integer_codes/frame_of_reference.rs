@@ -0,0 +1,224 @@
+use super::bit_vector_iterator::BitVectorIterator;
+use super::{gaps, push_prefix_sums, DeltaCode, IntegerCode, ListCode};
+use crate::introduction::bit_vector::{BitVector, BitVectorBuilder};
+use crate::introduction::util::msb;
+
+/// pack N values minus min on W bits each into the words
+type PackFn = fn(values: &[u64], min: u64, words: &mut [u64]);
+/// unpack N values packed on W bits each and add min to them
+type UnpackFn = fn(words: &[u64], min: u64, out: &mut [u64]);
+
+/// table of the routines specialized for the widths 0 to 64
+macro_rules! width_table {
+    ($f:ident, $n:ident, $t:ty) => {
+        width_table!($f, $n, $t; 0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22
+            23 24 25 26 27 28 29 30 31 32 33 34 35 36 37 38 39 40 41 42 43 44 45 46 47 48 49
+            50 51 52 53 54 55 56 57 58 59 60 61 62 63 64)
+    };
+    ($f:ident, $n:ident, $t:ty; $($w:literal)*) => {
+        [$($f::<$n, $w> as $t,)*]
+    };
+}
+
+/// with N and W known at compile time, the word indices and shifts are constants
+/// and the loop can be fully unrolled
+fn pack<const N: usize, const W: usize>(values: &[u64], min: u64, words: &mut [u64]) {
+    if W == 0 {
+        return;
+    }
+    words[..(N * W).div_ceil(64)].fill(0);
+    for (i, &x) in values[..N].iter().enumerate() {
+        let v = x - min;
+        let bit = i * W;
+        let (word, shift) = (bit / 64, bit % 64);
+        words[word] |= v << shift;
+        if shift + W > 64 {
+            words[word + 1] |= v >> (64 - shift);
+        }
+    }
+}
+
+fn unpack<const N: usize, const W: usize>(words: &[u64], min: u64, out: &mut [u64]) {
+    if W == 0 {
+        out[..N].fill(min);
+        return;
+    }
+    let mask = u64::MAX >> (64 - W);
+    for (i, x) in out[..N].iter_mut().enumerate() {
+        let bit = i * W;
+        let (word, shift) = (bit / 64, bit % 64);
+        let mut v = words[word] >> shift;
+        if shift + W > 64 {
+            v |= words[word + 1] << (64 - shift);
+        }
+        *x = min + (v & mask);
+    }
+}
+
+/// Frame-of-reference coding of blocks of N integers: the minimum of the block is
+/// subtracted from all values, which are then packed on the bit width w of the largest one.
+/// The packing routines are specialized for each width.
+pub struct FrameOfReference<const N: usize>;
+
+impl<const N: usize> FrameOfReference<N> {
+    const PACK: [PackFn; 65] = width_table!(pack, N, PackFn);
+    const UNPACK: [UnpackFn; 65] = width_table!(unpack, N, UnpackFn);
+
+    /// bit width of the largest value minus min
+    fn width(block: &[u64], min: u64) -> usize {
+        let max_diff = block.iter().map(|&x| x - min).max().unwrap_or(0);
+        if max_diff == 0 {
+            0
+        } else {
+            msb(max_diff) + 1
+        }
+    }
+
+    /// append a block of at most N values to the builder: the minimum in delta code,
+    /// the width on 7 bits, then the N packed values, the missing ones being equal to the minimum
+    pub fn encode(builder: &mut BitVectorBuilder, block: &[u64]) {
+        debug_assert!(!block.is_empty() && block.len() <= N);
+        let min = block.iter().copied().min().unwrap();
+        let w = Self::width(block, min);
+        let mut values = [min; N];
+        values[..block.len()].copy_from_slice(block);
+        // N values on at most 64 bits each
+        let mut words = [0u64; N];
+        Self::PACK[w](&values, min, &mut words);
+
+        DeltaCode::write(builder, min);
+        builder.append_bits(w as u64, 7);
+        let num_bits = N * w;
+        for (i, &word) in words[..num_bits.div_ceil(64)].iter().enumerate() {
+            builder.append_bits(word, (num_bits - 64 * i).min(64));
+        }
+    }
+
+    /// decode the N values of the block written by `encode`
+    pub fn decode(it: &mut BitVectorIterator, out: &mut [u64; N]) {
+        let min = DeltaCode::read(it);
+        let w = it.take(7) as usize;
+        let num_bits = N * w;
+        let mut words = [0u64; N];
+        for (i, word) in words[..num_bits.div_ceil(64)].iter_mut().enumerate() {
+            *word = it.take((num_bits - 64 * i).min(64));
+        }
+        Self::UNPACK[w](&words, min, out);
+    }
+
+    /// return the i-th value of the block written at position pos, without decoding the others
+    pub fn access(bv: &BitVector, pos: usize, i: usize) -> u64 {
+        debug_assert!(i < N);
+        let mut it = BitVectorIterator::new(bv, pos);
+        let min = DeltaCode::read(&mut it);
+        let w = it.take(7) as usize;
+        min + bv.get_bits(it.position() + i * w, w)
+    }
+
+    /// number of bits taken by the block
+    pub fn num_bits(block: &[u64]) -> usize {
+        let min = block.iter().copied().min().unwrap();
+        DeltaCode::bits(min) + 7 + N * Self::width(block, min)
+    }
+}
+
+impl<const N: usize> ListCode for FrameOfReference<N> {
+    /// the 32-bit size of the list, then its gaps in frame-of-reference blocks of N
+    fn write_list(builder: &mut BitVectorBuilder, list: &[u64]) {
+        builder.append_bits(list.len() as u64, 32);
+        for block in gaps(list).chunks(N) {
+            Self::encode(builder, block);
+        }
+    }
+
+    fn read_list(it: &mut BitVectorIterator, out: &mut Vec<u64>) {
+        let list_size = it.take(32) as usize;
+        let mut block = [0; N];
+        let mut prev_x = 0;
+        let mut remaining = list_size;
+        while remaining > 0 {
+            Self::decode(it, &mut block);
+            let n = remaining.min(N);
+            push_prefix_sums(out, &mut prev_x, &block[..n]);
+            remaining -= n;
+        }
+    }
+}
+
+pub type FrameOfReference32 = FrameOfReference<32>;
+pub type FrameOfReference64 = FrameOfReference<64>;
+pub type FrameOfReference128 = FrameOfReference<128>;
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_utils::{check_lists, random_list};
+    use super::*;
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    fn random_blocks<const N: usize>(seed: u64) -> Vec<Vec<u64>> {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let mut blocks: Vec<Vec<u64>> = (0..=64)
+            .map(|w| {
+                let (min, max_diff) = match w {
+                    0 => (rng.gen_range(0..1000), 0),
+                    64 => (0, u64::MAX),
+                    _ => (rng.gen_range(0..1000), u64::MAX >> (64 - w)),
+                };
+                (0..N).map(|_| min + rng.gen_range(0..=max_diff)).collect()
+            })
+            .collect();
+        blocks.push(vec![u64::MAX; N]);
+        blocks.push(vec![0, u64::MAX]);
+        blocks.push(vec![5, 3, 1000]);
+        blocks
+    }
+
+    fn check<const N: usize>() {
+        let blocks = random_blocks::<N>(N as u64);
+        let mut builder = BitVectorBuilder::new();
+        builder.append_bits(0, 3);
+        let mut positions = Vec::new();
+        for block in &blocks {
+            positions.push(builder.num_bits());
+            FrameOfReference::<N>::encode(&mut builder, block);
+            assert_eq!(
+                builder.num_bits() - positions.last().unwrap(),
+                FrameOfReference::<N>::num_bits(block)
+            );
+        }
+        let mut bv = BitVector::new();
+        builder.build(&mut bv);
+
+        let mut it = BitVectorIterator::new(&bv, 3);
+        let mut out = [0; N];
+        for (block, &pos) in blocks.iter().zip(&positions) {
+            FrameOfReference::<N>::decode(&mut it, &mut out);
+            assert_eq!(out[..block.len()], *block);
+            for (i, &x) in block.iter().enumerate() {
+                assert_eq!(FrameOfReference::<N>::access(&bv, pos, i), x);
+            }
+        }
+        assert_eq!(it.position(), bv.num_bits());
+    }
+
+    #[test]
+    fn frame_of_reference() {
+        check::<32>();
+        check::<64>();
+        check::<128>();
+        check::<256>();
+    }
+
+    #[test]
+    fn frame_of_reference_lists() {
+        let lists = [
+            vec![],
+            vec![0],
+            vec![3, 4, 5, 1 << 40],
+            random_list(1000, 0..100, 7),
+        ];
+        check_lists::<FrameOfReference64>(&lists);
+        check_lists::<FrameOfReference<256>>(&lists);
+    }
+}
@@ -0,0 +1,139 @@
+use super::bit_vector_iterator::BitVectorIterator;
+use super::{read_minimal_binary, write_minimal_binary, DeltaCode, IntegerCode, ListCode};
+use crate::introduction::bit_vector::BitVectorBuilder;
+
+/// Binary Interpolative Coding (Moffat and Stuiver, 2000) of strictly increasing lists.
+///
+/// The middle value of the list is written with the minimal binary code in the range
+/// implied by the bounds [lo, hi] of the list and its position, then both halves are coded
+/// recursively with tighter bounds. Runs of consecutive values thus take no bits at all.
+pub struct InterpolativeCode;
+
+impl InterpolativeCode {
+    /// append the strictly increasing values to the builder:
+    /// the last value in delta code, then the other values in [0, last-1]
+    pub fn encode(builder: &mut BitVectorBuilder, values: &[u64]) {
+        assert!(
+            values.windows(2).all(|w| w[0] < w[1]),
+            "the values must be strictly increasing"
+        );
+        if let Some((&last, values)) = values.split_last() {
+            DeltaCode::write(builder, last);
+            if !values.is_empty() {
+                Self::write_rec(builder, values, 0, last - 1);
+            }
+        }
+    }
+
+    /// write the values, all in [lo, hi]
+    fn write_rec(builder: &mut BitVectorBuilder, values: &[u64], lo: u64, hi: u64) {
+        let n = values.len() as u64;
+        debug_assert!(hi - lo + 1 >= n);
+        if hi - lo + 1 == n {
+            return; // all the values in [lo, hi] are present
+        }
+        let mid = values.len() / 2;
+        let x = values[mid];
+        // there are mid values before x and n-mid-1 values after it
+        let x_lo = lo + mid as u64;
+        let x_hi = hi - (n - mid as u64 - 1);
+        write_minimal_binary(builder, x - x_lo, x_hi - x_lo);
+        if mid > 0 {
+            Self::write_rec(builder, &values[..mid], lo, x - 1);
+        }
+        if mid + 1 < values.len() {
+            Self::write_rec(builder, &values[mid + 1..], x + 1, hi);
+        }
+    }
+
+    /// decode n values written by `encode` and append them to out
+    pub fn decode(it: &mut BitVectorIterator, n: usize, out: &mut Vec<u64>) {
+        if n == 0 {
+            return;
+        }
+        let last = DeltaCode::read(it);
+        let start = out.len();
+        out.resize(start + n, 0);
+        out[start + n - 1] = last;
+        if n > 1 {
+            Self::read_rec(it, &mut out[start..start + n - 1], 0, last - 1);
+        }
+    }
+
+    /// fill values with integers in [lo, hi]
+    fn read_rec(it: &mut BitVectorIterator, values: &mut [u64], lo: u64, hi: u64) {
+        let n = values.len() as u64;
+        if hi - lo + 1 == n {
+            for (v, x) in values.iter_mut().zip(lo..) {
+                *v = x;
+            }
+            return;
+        }
+        let mid = values.len() / 2;
+        let x_lo = lo + mid as u64;
+        let x_hi = hi - (n - mid as u64 - 1);
+        let x = x_lo + read_minimal_binary(it, x_hi - x_lo);
+        values[mid] = x;
+        let (left, right) = values.split_at_mut(mid);
+        if !left.is_empty() {
+            Self::read_rec(it, left, lo, x - 1);
+        }
+        if right.len() > 1 {
+            Self::read_rec(it, &mut right[1..], x + 1, hi);
+        }
+    }
+}
+
+impl ListCode for InterpolativeCode {
+    /// the 32-bit size of the list followed by `InterpolativeCode::encode`,
+    /// which panics if the list is not strictly increasing
+    fn write_list(builder: &mut BitVectorBuilder, list: &[u64]) {
+        builder.append_bits(list.len() as u64, 32);
+        Self::encode(builder, list);
+    }
+
+    fn read_list(it: &mut BitVectorIterator, out: &mut Vec<u64>) {
+        let list_size = it.take(32) as usize;
+        Self::decode(it, list_size, out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_utils::random_list;
+    use super::*;
+    use crate::introduction::bit_vector::BitVector;
+
+    #[test]
+    fn interpolative() {
+        let lists = [
+            vec![],
+            vec![0],
+            vec![7],
+            vec![0, 1, 2, 3, 4],
+            vec![3, 4, 5, 1000, 1001, 1 << 50],
+            random_list(10_000, 1..100, 11),
+        ];
+
+        let mut builder = BitVectorBuilder::new();
+        for values in &lists {
+            InterpolativeCode::encode(&mut builder, values);
+        }
+        let mut bv = BitVector::new();
+        builder.build(&mut bv);
+
+        let mut it = BitVectorIterator::new(&bv, 0);
+        for values in &lists {
+            let mut decoded = vec![42];
+            InterpolativeCode::decode(&mut it, values.len(), &mut decoded);
+            assert_eq!(decoded[1..], *values);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "strictly increasing")]
+    fn interpolative_duplicates() {
+        let mut builder = BitVectorBuilder::new();
+        InterpolativeCode::write_list(&mut builder, &[1, 1, 5]);
+    }
+}
@@ -1,103 +1,811 @@
 pub mod bit_vector_iterator;
 pub mod check;
 pub mod compress;
+pub mod compressed_lists;
 pub mod decompress;
+pub mod elias_fano;
+pub mod frame_of_reference;
+pub mod interpolative;
+pub mod partitioned_elias_fano;
+pub mod pfor;
+pub mod simple;
+pub mod skip_pointers;
+#[cfg(test)]
+mod test_utils;
+pub mod varint;
 
 use crate::introduction::bit_vector::BitVectorBuilder;
 use crate::introduction::util::msb;
 use bit_vector_iterator::BitVectorIterator;
 use num_traits::int::PrimInt;
+use std::marker::PhantomData;
 
 pub trait IntegerCode<T: PrimInt> {
     fn write(builder: &mut BitVectorBuilder, x: T);
     fn read(it: &mut BitVectorIterator) -> T;
+    /// number of bits taken by the codeword of x
+    fn bits(x: T) -> usize;
 }
 
+/// integer codes whose parameters are only known at runtime, and carried by the code itself
+pub trait DynIntegerCode<T: PrimInt> {
+    fn write(&self, builder: &mut BitVectorBuilder, x: T);
+    fn read(&self, it: &mut BitVectorIterator) -> T;
+    fn bits(&self, x: T) -> usize;
+}
+
+/// codes for a whole list of integers, which write their own header (e.g., the list size)
+pub trait ListCode {
+    fn write_list(builder: &mut BitVectorBuilder, list: &[u64]);
+    /// decode the next list and append it to out
+    fn read_list(it: &mut BitVectorIterator, out: &mut Vec<u64>);
+}
+
+/// gaps between consecutive values of a sorted list, the first gap being the first value
+pub(super) fn gaps(list: &[u64]) -> Vec<u64> {
+    let mut prev_x = 0;
+    list.iter()
+        .map(|&x| {
+            debug_assert!(x >= prev_x);
+            let gap = x - prev_x;
+            prev_x = x;
+            gap
+        })
+        .collect()
+}
+
+/// append to out the values whose gaps follow prev_x, and move prev_x to the last one
+pub(super) fn push_prefix_sums(out: &mut Vec<u64>, prev_x: &mut u64, gaps: &[u64]) {
+    for &gap in gaps {
+        *prev_x += gap;
+        out.push(*prev_x);
+    }
+}
+
+/// any integer code can code a sorted list: its 32-bit size followed by the gaps between values
+impl<C: IntegerCode<u64>> ListCode for C {
+    fn write_list(builder: &mut BitVectorBuilder, list: &[u64]) {
+        builder.append_bits(list.len() as u64, 32);
+        let mut prev_x = 0;
+        for &x in list {
+            debug_assert!(x >= prev_x);
+            C::write(builder, x - prev_x);
+            prev_x = x;
+        }
+    }
+
+    fn read_list(it: &mut BitVectorIterator, out: &mut Vec<u64>) {
+        let list_size = it.take(32);
+        let mut prev_x = 0;
+        for _ in 0..list_size {
+            let x = C::read(it) + prev_x;
+            out.push(x);
+            prev_x = x;
+        }
+    }
+}
+
+/// x zeros followed by a one: runs longer than 64 bits are written as whole zero words,
+/// then the terminator, and read back by `BitVectorIterator::skip_zeros`.
+/// The length of the codeword of u64::MAX does not fit in a usize, so `bits` saturates.
 pub struct UnaryCode;
 
+impl IntegerCode<u64> for UnaryCode {
+    fn write(builder: &mut BitVectorBuilder, mut x: u64) {
+        while x >= 64 {
+            builder.append_bits(0, 64);
+            x -= 64;
+        }
+        builder.append_bits(1u64 << x, x as usize + 1);
+    }
+
+    fn read(it: &mut BitVectorIterator) -> u64 {
+        it.skip_zeros() as u64
+    }
+
+    fn bits(x: u64) -> usize {
+        (x as usize).saturating_add(1)
+    }
+}
+
 impl IntegerCode<usize> for UnaryCode {
     fn write(builder: &mut BitVectorBuilder, x: usize) {
-        debug_assert!(x < 64);
-        let u = 1u64 << x;
-        builder.append_bits(u, x + 1);
+        UnaryCode::write(builder, x as u64);
     }
 
     fn read(it: &mut BitVectorIterator) -> usize {
         it.skip_zeros()
     }
+
+    fn bits(x: usize) -> usize {
+        x.saturating_add(1)
+    }
+}
+
+/// write the integer x <= r using b=ceil(log2(r+1)) bits
+pub fn write_binary(builder: &mut BitVectorBuilder, x: u64, r: u64) {
+    debug_assert!(r > 0);
+    debug_assert!(x <= r);
+    let b = msb(r) + 1;
+    builder.append_bits(x, b);
+}
+
+/// read b=ceil(log2(r+1)) bits and interprets them as the integer x
+pub fn read_binary(it: &mut BitVectorIterator, r: u64) -> u64 {
+    debug_assert!(r > 0);
+    let b = msb(r) + 1;
+    let x = it.take(b);
+    debug_assert!(x <= r);
+    x
 }
 
 pub struct BinaryCode<const R: u64>;
 
 impl<const R: u64> IntegerCode<u64> for BinaryCode<R> {
-    /// write the integer x <= r using b=ceil(log2(r+1)) bits
     fn write(builder: &mut BitVectorBuilder, x: u64) {
-        debug_assert!(R > 0);
-        debug_assert!(x <= R);
-        let b = msb(R) + 1;
-        builder.append_bits(x, b);
+        write_binary(builder, x, R);
     }
 
-    /// read b=ceil(log2(r+1)) bits and interprets them as the integer x
     fn read(it: &mut BitVectorIterator) -> u64 {
-        debug_assert!(R > 0);
-        let b = msb(R) + 1;
-        let x = it.take(b);
-        debug_assert!(x <= R);
+        read_binary(it, R)
+    }
+
+    fn bits(_x: u64) -> usize {
+        msb(R) + 1
+    }
+}
+
+/// BinaryCode with a range r chosen at runtime
+pub struct DynBinaryCode {
+    pub r: u64,
+}
+
+impl DynIntegerCode<u64> for DynBinaryCode {
+    fn write(&self, builder: &mut BitVectorBuilder, x: u64) {
+        write_binary(builder, x, self.r);
+    }
+
+    fn read(&self, it: &mut BitVectorIterator) -> u64 {
+        read_binary(it, self.r)
+    }
+
+    fn bits(&self, _x: u64) -> usize {
+        msb(self.r) + 1
+    }
+}
+
+/// write the integer x <= r with the minimal (truncated) binary code,
+/// i.e., using floor(log2(r+1)) bits for the first values and ceil(log2(r+1)) for the others
+pub fn write_minimal_binary(builder: &mut BitVectorBuilder, x: u64, r: u64) {
+    debug_assert!(x <= r);
+    if r == 0 {
+        return;
+    }
+    let b = msb(r) + 1;
+    let t = (u64::MAX >> (64 - b)) - r; // number of values written with b-1 bits
+    if x < t {
+        builder.append_bits(x, b - 1);
+    } else {
+        // the first b-1 bits must be >= t so that the reader knows it has to read one more
+        let y = x + t;
+        builder.append_bits(y >> 1, b - 1);
+        builder.append_bits(y & 1, 1);
+    }
+}
+
+/// read an integer x <= r written with the minimal (truncated) binary code
+pub fn read_minimal_binary(it: &mut BitVectorIterator, r: u64) -> u64 {
+    if r == 0 {
+        return 0;
+    }
+    let b = msb(r) + 1;
+    let t = (u64::MAX >> (64 - b)) - r;
+    let x = it.take(b - 1);
+    if x < t {
         x
+    } else {
+        ((x << 1) | it.take(1)) - t
+    }
+}
+
+/// number of bits taken by x <= r in minimal binary code
+pub fn minimal_binary_bits(x: u64, r: u64) -> usize {
+    if r == 0 {
+        return 0;
+    }
+    let b = msb(r) + 1;
+    let t = (u64::MAX >> (64 - b)) - r;
+    if x < t {
+        b - 1
+    } else {
+        b
+    }
+}
+
+/// minimal binary code of the integers x <= R, unlike BinaryCode<R> it wastes no bits
+/// when R+1 is not a power of two
+pub struct MinimalBinaryCode<const R: u64>;
+
+impl<const R: u64> IntegerCode<u64> for MinimalBinaryCode<R> {
+    fn write(builder: &mut BitVectorBuilder, x: u64) {
+        write_minimal_binary(builder, x, R);
+    }
+
+    fn read(it: &mut BitVectorIterator) -> u64 {
+        read_minimal_binary(it, R)
+    }
+
+    fn bits(x: u64) -> usize {
+        minimal_binary_bits(x, R)
+    }
+}
+
+/// MinimalBinaryCode with a range r chosen at runtime
+pub struct DynMinimalBinaryCode {
+    pub r: u64,
+}
+
+impl DynIntegerCode<u64> for DynMinimalBinaryCode {
+    fn write(&self, builder: &mut BitVectorBuilder, x: u64) {
+        write_minimal_binary(builder, x, self.r);
+    }
+
+    fn read(&self, it: &mut BitVectorIterator) -> u64 {
+        read_minimal_binary(it, self.r)
+    }
+
+    fn bits(&self, x: u64) -> usize {
+        minimal_binary_bits(x, self.r)
+    }
+}
+
+/// b = floor(log2(x+1)), which is 64 for x = u64::MAX
+fn succ_msb(x: u64) -> usize {
+    if x == u64::MAX {
+        64
+    } else {
+        msb(x + 1)
     }
 }
 
+/// the b bits of x+1 below its leading 1, with b = succ_msb(x)
+fn succ_low_bits(x: u64, b: usize) -> u64 {
+    let mask = 1u64.checked_shl(b as u32).unwrap_or(0).wrapping_sub(1);
+    x.wrapping_add(1) & mask
+}
+
+/// inverse of succ_low_bits: x such that x+1 = 2^b + low
+fn from_succ_low_bits(low: u64, b: usize) -> u64 {
+    (low | 1u64.checked_shl(b as u32).unwrap_or(0)).wrapping_sub(1)
+}
+
+/// Elias gamma code: b = floor(log2(x+1)) in unary, then the b bits of x+1 below its leading 1.
+/// For x = u64::MAX, x+1 = 2^64 is written as 64 zeros, the terminator, then 64 zeros.
 pub struct GammaCode;
 
 impl IntegerCode<u64> for GammaCode {
     fn write(builder: &mut BitVectorBuilder, x: u64) {
-        let xx = x + 1;
-        let b = msb(xx);
-        UnaryCode::write(builder, b);
-        let mask = (1u64 << b).wrapping_sub(1);
-        builder.append_bits(xx & mask, b);
+        let b = succ_msb(x);
+        UnaryCode::write(builder, b as u64);
+        builder.append_bits(succ_low_bits(x, b), b);
     }
 
     fn read(it: &mut BitVectorIterator) -> u64 {
-        let b = UnaryCode::read(it);
-        (it.take(b) | (1 << b)).wrapping_sub(1)
+        let b = it.skip_zeros();
+        debug_assert!(b <= 64);
+        from_succ_low_bits(it.take(b), b)
+    }
+
+    fn bits(x: u64) -> usize {
+        2 * succ_msb(x) + 1
     }
 }
 
+/// Elias delta code: like GammaCode, but with b = floor(log2(x+1)) in gamma code
 pub struct DeltaCode;
 
 impl IntegerCode<u64> for DeltaCode {
     fn write(builder: &mut BitVectorBuilder, x: u64) {
-        let xx = x + 1;
-        let b = msb(xx);
+        let b = succ_msb(x);
         GammaCode::write(builder, b as u64);
-        let mask = (1u64 << b).wrapping_sub(1);
-        builder.append_bits(xx & mask, b);
+        builder.append_bits(succ_low_bits(x, b), b);
     }
 
     fn read(it: &mut BitVectorIterator) -> u64 {
         let b = GammaCode::read(it) as usize;
-        (it.take(b) | (1 << b)).wrapping_sub(1)
+        debug_assert!(b <= 64);
+        from_succ_low_bits(it.take(b), b)
+    }
+
+    fn bits(x: u64) -> usize {
+        let b = succ_msb(x);
+        GammaCode::bits(b as u64) + b
+    }
+}
+
+/// Elias omega code: x+1 is written as a sequence of groups, each group being the binary
+/// representation of the length of the next one, followed by a 0
+pub struct OmegaCode;
+
+impl IntegerCode<u64> for OmegaCode {
+    fn write(builder: &mut BitVectorBuilder, x: u64) {
+        // the groups from the last one, as their bits below the leading 1 and their number:
+        // x+1 <= 2^64 has at most 4 groups, e.g., 2^64, 64, 6 and 2
+        let mut groups = [(0, 0); 4];
+        let mut c = 0;
+        let mut b = succ_msb(x);
+        let mut low = succ_low_bits(x, b);
+        while b > 0 {
+            groups[c] = (low, b);
+            c += 1;
+            // the previous group is the length b of this one
+            let n = b as u64;
+            b = msb(n);
+            low = n - (1 << b);
+        }
+        // each group starts with its leading 1, followed by its other bits
+        for &(low, b) in groups[..c].iter().rev() {
+            builder.append_bits(1, 1);
+            builder.append_bits(low, b);
+        }
+        builder.append_bits(0, 1);
+    }
+
+    fn read(it: &mut BitVectorIterator) -> u64 {
+        // x+1 is the last group read, starting from 1
+        let mut x = 0;
+        while it.take(1) == 1 {
+            let b = x as usize + 1;
+            x = from_succ_low_bits(it.take(b), b);
+        }
+        x
     }
+
+    fn bits(x: u64) -> usize {
+        let mut bits = 1;
+        let mut b = succ_msb(x);
+        while b > 0 {
+            bits += b + 1;
+            b = msb(b as u64);
+        }
+        bits
+    }
+}
+
+/// Levenshtein code: the number c of groups in unary (as c ones followed by a 0),
+/// then the groups, each one being x without its leading 1 and the length of the next one
+pub struct LevenshteinCode;
+
+impl IntegerCode<u64> for LevenshteinCode {
+    fn write(builder: &mut BitVectorBuilder, x: u64) {
+        // the groups from the last one, as their bits below the leading 1 and their number:
+        // x < 2^64 has at most 5 groups, e.g., 2^64-1, 63, 5, 2 and 1
+        let mut groups = [(0, 0); 5];
+        let mut c = 0;
+        let mut n = x;
+        while n > 0 {
+            let b = msb(n);
+            groups[c] = (n - (1 << b), b);
+            c += 1;
+            n = b as u64;
+        }
+        builder.append_bits((1u64 << c) - 1, c + 1);
+        for &(low, b) in groups[..c].iter().rev() {
+            builder.append_bits(low, b);
+        }
+    }
+
+    fn read(it: &mut BitVectorIterator) -> u64 {
+        let mut c = 0;
+        while it.take(1) == 1 {
+            c += 1;
+        }
+        if c == 0 {
+            return 0;
+        }
+        let mut n = 1;
+        for _ in 1..c {
+            let b = n as usize;
+            n = it.take(b) | (1 << b);
+        }
+        n
+    }
+
+    fn bits(x: u64) -> usize {
+        let mut bits = 1;
+        let mut n = x;
+        while n > 0 {
+            bits += msb(n) + 1;
+            n = msb(n) as u64;
+        }
+        bits
+    }
+}
+
+/// FIBONACCI[i] is the (i+2)-th Fibonacci number, i.e., 1, 2, 3, 5, 8, ...
+const FIBONACCI: [u64; 92] = {
+    let mut fib = [0; 92];
+    fib[0] = 1;
+    fib[1] = 2;
+    let mut i = 2;
+    while i < 92 {
+        fib[i] = fib[i - 1] + fib[i - 2];
+        i += 1;
+    }
+    fib
+};
+
+/// Fibonacci code: the Zeckendorf representation of x+1, i.e., as a sum of
+/// non-consecutive Fibonacci numbers, from the smallest one, terminated by an extra 1
+pub struct FibonacciCode;
+
+impl IntegerCode<u64> for FibonacciCode {
+    fn write(builder: &mut BitVectorBuilder, x: u64) {
+        // the largest Fibonacci number f <= x+1, subtracted as f-1 from x since x+1 may be 2^64
+        let last = FIBONACCI.partition_point(|&f| f - 1 <= x) - 1;
+        let mut code = 1u128 << last; // the bits of the representation, at most 92
+        let mut n = x - (FIBONACCI[last] - 1);
+        while n > 0 {
+            let i = FIBONACCI.partition_point(|&f| f <= n) - 1;
+            code |= 1 << i;
+            n -= FIBONACCI[i];
+        }
+        let len = last + 1;
+        builder.append_bits(code as u64, len.min(64));
+        if len > 64 {
+            builder.append_bits((code >> 64) as u64, len - 64);
+        }
+        builder.append_bits(1, 1);
+    }
+
+    fn read(it: &mut BitVectorIterator) -> u64 {
+        // x+1 = 2^64 wraps to 0, and x to u64::MAX
+        let mut n = 0u64;
+        let mut prev_bit = 0;
+        let mut i = 0;
+        loop {
+            let bit = it.take(1);
+            if bit == 1 && prev_bit == 1 {
+                break;
+            }
+            n = n.wrapping_add(bit * FIBONACCI[i]);
+            prev_bit = bit;
+            i += 1;
+        }
+        n.wrapping_sub(1)
+    }
+
+    fn bits(x: u64) -> usize {
+        // the largest Fibonacci number in the representation gives its length
+        FIBONACCI.partition_point(|&f| f - 1 <= x) + 1
+    }
+}
+
+/// interval [lo, lo + r] of the values x whose zeta code of shrinking factor k starts with h,
+/// i.e., with 2^(hk) <= x+1 < 2^((h+1)k), capped to u64::MAX
+fn zeta_interval(h: usize, k: usize) -> (u64, u64) {
+    let lo = 1u64
+        .checked_shl((h * k) as u32)
+        .unwrap_or(0)
+        .wrapping_sub(1);
+    let e = (h + 1) * k;
+    let hi = if e > 64 {
+        u64::MAX
+    } else {
+        (u64::MAX >> (64 - e)) - 1
+    };
+    (lo, hi - lo)
+}
+
+/// number of bits of the offsets in [0, r] of the interval h when they are written in plain
+/// binary rather than in minimal binary: when r+1 is a power of two (i.e., k = 1), and when
+/// hk = 64, whose interval only holds u64::MAX but which takes hk bits as in gamma code
+fn zeta_plain_bits(h: usize, k: usize, r: u64) -> Option<usize> {
+    if h * k == 64 {
+        Some(64)
+    } else if r > 0 && r & r.wrapping_add(1) == 0 {
+        Some(msb(r) + 1)
+    } else {
+        None
+    }
+}
+
+/// write x with the zeta code of shrinking factor k (Boldi and Vigna, 2005):
+/// h = floor(log2(x+1) / k) in unary, then x+1 - 2^(hk) in minimal binary,
+/// or in plain binary when the interval size is a power of two (i.e., k = 1)
+pub fn write_zeta(builder: &mut BitVectorBuilder, x: u64, k: usize) {
+    debug_assert!(k > 0);
+    let h = succ_msb(x) / k;
+    let (lo, r) = zeta_interval(h, k);
+    UnaryCode::write(builder, h as u64);
+    match zeta_plain_bits(h, k, r) {
+        Some(b) => builder.append_bits(x - lo, b),
+        None => write_minimal_binary(builder, x - lo, r),
+    }
+}
+
+pub fn read_zeta(it: &mut BitVectorIterator, k: usize) -> u64 {
+    debug_assert!(k > 0);
+    let h = it.skip_zeros();
+    let (lo, r) = zeta_interval(h, k);
+    let offset = match zeta_plain_bits(h, k, r) {
+        Some(b) => it.take(b),
+        None => read_minimal_binary(it, r),
+    };
+    lo + offset
+}
+
+/// number of bits taken by x in zeta code of shrinking factor k
+pub fn zeta_bits(x: u64, k: usize) -> usize {
+    let h = succ_msb(x) / k;
+    let (lo, r) = zeta_interval(h, k);
+    let offset_bits = match zeta_plain_bits(h, k, r) {
+        Some(b) => b,
+        None => minimal_binary_bits(x - lo, r),
+    };
+    h + 1 + offset_bits
+}
+
+/// zeta code with shrinking factor K, ZetaCode<1> being the same as GammaCode
+pub struct ZetaCode<const K: usize>;
+
+impl<const K: usize> IntegerCode<u64> for ZetaCode<K> {
+    fn write(builder: &mut BitVectorBuilder, x: u64) {
+        write_zeta(builder, x, K);
+    }
+
+    fn read(it: &mut BitVectorIterator) -> u64 {
+        read_zeta(it, K)
+    }
+
+    fn bits(x: u64) -> usize {
+        zeta_bits(x, K)
+    }
+}
+
+/// ZetaCode with a shrinking factor k chosen at runtime
+pub struct DynZetaCode {
+    pub k: usize,
+}
+
+impl DynIntegerCode<u64> for DynZetaCode {
+    fn write(&self, builder: &mut BitVectorBuilder, x: u64) {
+        write_zeta(builder, x, self.k);
+    }
+
+    fn read(&self, it: &mut BitVectorIterator) -> u64 {
+        read_zeta(it, self.k)
+    }
+
+    fn bits(&self, x: u64) -> usize {
+        zeta_bits(x, self.k)
+    }
+}
+
+/// write x = q*2^k + r with the quotient q in gamma code and the remainder r on k bits
+pub fn write_rice(builder: &mut BitVectorBuilder, x: u64, k: usize) {
+    debug_assert!(k > 0);
+    let q = x >> k;
+    let r = x - (q << k);
+    GammaCode::write(builder, q);
+    builder.append_bits(r, k);
+}
+
+pub fn read_rice(it: &mut BitVectorIterator, k: usize) -> u64 {
+    debug_assert!(k > 0);
+    let q = GammaCode::read(it);
+    let r = it.take(k);
+    r + (q << k)
+}
+
+/// number of bits taken by x in Rice code with parameter k
+pub fn rice_bits(x: u64, k: usize) -> usize {
+    GammaCode::bits(x >> k) + k
 }
 
 pub struct RiceCode<const K: usize>;
 
 impl<const K: usize> IntegerCode<u64> for RiceCode<K> {
     fn write(builder: &mut BitVectorBuilder, x: u64) {
-        debug_assert!(K > 0);
-        let q = x >> K;
-        let r = x - (q << K);
-        GammaCode::write(builder, q);
-        builder.append_bits(r, K);
+        write_rice(builder, x, K);
     }
 
     fn read(it: &mut BitVectorIterator) -> u64 {
-        debug_assert!(K > 0);
-        let q = GammaCode::read(it);
-        let r = it.take(K);
-        r + (q << K)
+        read_rice(it, K)
+    }
+
+    fn bits(x: u64) -> usize {
+        rice_bits(x, K)
+    }
+}
+
+/// RiceCode with a parameter k chosen at runtime
+pub struct DynRiceCode {
+    pub k: usize,
+}
+
+impl DynIntegerCode<u64> for DynRiceCode {
+    fn write(&self, builder: &mut BitVectorBuilder, x: u64) {
+        write_rice(builder, x, self.k);
+    }
+
+    fn read(&self, it: &mut BitVectorIterator) -> u64 {
+        read_rice(it, self.k)
+    }
+
+    fn bits(&self, x: u64) -> usize {
+        rice_bits(x, self.k)
+    }
+}
+
+/// number of bits taken by the gaps in Rice code with parameter k
+pub fn rice_cost(gaps: &[u64], k: usize) -> usize {
+    gaps.iter().map(|&x| rice_bits(x, k)).sum()
+}
+
+/// Rice parameter k > 0 minimizing the exact cost of the gaps
+pub fn optimal_rice_parameter(gaps: &[u64]) -> usize {
+    let max_gap = gaps.iter().copied().max().unwrap_or(0);
+    let max_k = (succ_msb(max_gap) + 1).min(63);
+    (1..=max_k).min_by_key(|&k| rice_cost(gaps, k)).unwrap()
+}
+
+/// Rice code of the gaps with the best parameter k for each list,
+/// written on 6 bits after the 32-bit list size
+pub struct AdaptiveRiceCode;
+
+impl ListCode for AdaptiveRiceCode {
+    fn write_list(builder: &mut BitVectorBuilder, list: &[u64]) {
+        let gaps = gaps(list);
+        let k = optimal_rice_parameter(&gaps);
+        builder.append_bits(list.len() as u64, 32);
+        builder.append_bits(k as u64, 6);
+        let code = DynRiceCode { k };
+        for &gap in &gaps {
+            code.write(builder, gap);
+        }
+    }
+
+    fn read_list(it: &mut BitVectorIterator, out: &mut Vec<u64>) {
+        let list_size = it.take(32);
+        let code = DynRiceCode {
+            k: it.take(6) as usize,
+        };
+        let mut prev_x = 0;
+        for _ in 0..list_size {
+            let x = code.read(it) + prev_x;
+            out.push(x);
+            prev_x = x;
+        }
+    }
+}
+
+/// write x with the Exp-Golomb code of order k: w = x + 2^k is written as
+/// msb(w) - k in unary, then the msb(w) bits of w below its leading 1.
+/// It has the same codeword lengths as `write_rice`, whose quotient is in gamma code.
+///
+/// As w may not fit in 64 bits, it is split into w = (q+1) 2^k + r with q = x >> k:
+/// msb(w) - k = floor(log2(q+1)), and the bits of w below its leading 1 are r
+/// followed by the bits of q+1 below its leading 1.
+pub fn write_exp_golomb(builder: &mut BitVectorBuilder, x: u64, k: usize) {
+    debug_assert!(k < 64);
+    let q = x >> k;
+    let r = x - (q << k);
+    let b = succ_msb(q);
+    UnaryCode::write(builder, b as u64);
+    builder.append_bits(r, k);
+    builder.append_bits(succ_low_bits(q, b), b);
+}
+
+pub fn read_exp_golomb(it: &mut BitVectorIterator, k: usize) -> u64 {
+    debug_assert!(k < 64);
+    let b = it.skip_zeros();
+    let r = it.take(k);
+    let q = from_succ_low_bits(it.take(b), b);
+    (q << k) | r
+}
+
+/// number of bits taken by x in Exp-Golomb code of order k
+pub fn exp_golomb_bits(x: u64, k: usize) -> usize {
+    GammaCode::bits(x >> k) + k
+}
+
+pub struct ExpGolombCode<const K: usize>;
+
+impl<const K: usize> IntegerCode<u64> for ExpGolombCode<K> {
+    fn write(builder: &mut BitVectorBuilder, x: u64) {
+        write_exp_golomb(builder, x, K);
+    }
+
+    fn read(it: &mut BitVectorIterator) -> u64 {
+        read_exp_golomb(it, K)
+    }
+
+    fn bits(x: u64) -> usize {
+        exp_golomb_bits(x, K)
+    }
+}
+
+/// ExpGolombCode with an order k chosen at runtime
+pub struct DynExpGolombCode {
+    pub k: usize,
+}
+
+impl DynIntegerCode<u64> for DynExpGolombCode {
+    fn write(&self, builder: &mut BitVectorBuilder, x: u64) {
+        write_exp_golomb(builder, x, self.k);
+    }
+
+    fn read(&self, it: &mut BitVectorIterator) -> u64 {
+        read_exp_golomb(it, self.k)
+    }
+
+    fn bits(&self, x: u64) -> usize {
+        exp_golomb_bits(x, self.k)
+    }
+}
+
+/// write x = q*b + r with the quotient q in unary and the remainder r in minimal binary
+pub fn write_golomb(builder: &mut BitVectorBuilder, x: u64, b: u64) {
+    debug_assert!(b > 0);
+    UnaryCode::write(builder, x / b);
+    write_minimal_binary(builder, x % b, b - 1);
+}
+
+pub fn read_golomb(it: &mut BitVectorIterator, b: u64) -> u64 {
+    debug_assert!(b > 0);
+    let q = it.skip_zeros() as u64;
+    let r = read_minimal_binary(it, b - 1);
+    q * b + r
+}
+
+/// number of bits taken by x in Golomb code with modulus b,
+/// saturating at usize::MAX as the unary quotient may be that long
+pub fn golomb_bits(x: u64, b: u64) -> usize {
+    ((x / b) as usize).saturating_add(1 + minimal_binary_bits(x % b, b - 1))
+}
+
+/// Golomb parameter b ~ 0.69 * mean, optimal for geometrically distributed gaps
+pub fn optimal_golomb_parameter(gaps: &[u64]) -> u64 {
+    if gaps.is_empty() {
+        return 1;
+    }
+    let mean = gaps.iter().map(|&x| x as f64).sum::<f64>() / gaps.len() as f64;
+    ((0.69 * mean).ceil() as u64).max(1)
+}
+
+pub struct GolombCode<const B: u64>;
+
+impl<const B: u64> IntegerCode<u64> for GolombCode<B> {
+    fn write(builder: &mut BitVectorBuilder, x: u64) {
+        write_golomb(builder, x, B);
+    }
+
+    fn read(it: &mut BitVectorIterator) -> u64 {
+        read_golomb(it, B)
+    }
+
+    fn bits(x: u64) -> usize {
+        golomb_bits(x, B)
+    }
+}
+
+/// GolombCode with a modulus b chosen at runtime, e.g., by `optimal_golomb_parameter`
+pub struct DynGolombCode {
+    pub b: u64,
+}
+
+impl DynIntegerCode<u64> for DynGolombCode {
+    fn write(&self, builder: &mut BitVectorBuilder, x: u64) {
+        write_golomb(builder, x, self.b);
+    }
+
+    fn read(&self, it: &mut BitVectorIterator) -> u64 {
+        read_golomb(it, self.b)
+    }
+
+    fn bits(&self, x: u64) -> usize {
+        golomb_bits(x, self.b)
     }
 }
 
@@ -127,4 +835,626 @@ impl IntegerCode<u64> for VByteCode {
         }
         val
     }
+
+    fn bits(x: u64) -> usize {
+        8 * (msb(x | 1) / 7 + 1)
+    }
+}
+
+/// like VByteCode, but with groups of 4 bits: 3 data bits and a continuation bit
+pub struct NibbleCode;
+
+impl IntegerCode<u64> for NibbleCode {
+    fn write(builder: &mut BitVectorBuilder, x: u64) {
+        if x < 8 {
+            builder.append_bits(x, 4);
+            return;
+        }
+        let data_bits = x & 7;
+        builder.append_bits(data_bits | 8, 4);
+        Self::write(builder, x >> 3);
+    }
+
+    fn read(it: &mut BitVectorIterator) -> u64 {
+        let mut val = 0;
+        let mut shift = 0;
+        loop {
+            let nibble = it.take_one_nibble();
+            val += (nibble & 7) << shift;
+            if nibble < 8 {
+                break;
+            }
+            shift += 3;
+        }
+        val
+    }
+
+    fn bits(x: u64) -> usize {
+        4 * (msb(x | 1) / 3 + 1)
+    }
+}
+
+/// map signed integers to unsigned ones by interleaving them: 0, -1, 1, -2, 2, ...
+pub fn zigzag_encode(x: i64) -> u64 {
+    ((x << 1) ^ (x >> 63)) as u64
+}
+
+pub fn zigzag_decode(x: u64) -> i64 {
+    ((x >> 1) as i64) ^ -((x & 1) as i64)
+}
+
+/// code for signed integers: the zigzag mapping of x written with the code C,
+/// so that integers of small absolute value get short codewords
+pub struct ZigZagCode<C>(PhantomData<C>);
+
+impl<C: IntegerCode<u64>> IntegerCode<i64> for ZigZagCode<C> {
+    fn write(builder: &mut BitVectorBuilder, x: i64) {
+        C::write(builder, zigzag_encode(x));
+    }
+
+    fn read(it: &mut BitVectorIterator) -> i64 {
+        zigzag_decode(C::read(it))
+    }
+
+    fn bits(x: i64) -> usize {
+        C::bits(zigzag_encode(x))
+    }
+}
+
+/// code for unsorted lists: the 32-bit size followed by the signed differences
+/// between consecutive values, written with ZigZagCode<C>
+pub struct UnsortedListCode<C>(PhantomData<C>);
+
+impl<C: IntegerCode<u64>> ListCode for UnsortedListCode<C> {
+    fn write_list(builder: &mut BitVectorBuilder, list: &[u64]) {
+        builder.append_bits(list.len() as u64, 32);
+        let mut prev_x = 0u64;
+        for &x in list {
+            ZigZagCode::<C>::write(builder, x.wrapping_sub(prev_x) as i64);
+            prev_x = x;
+        }
+    }
+
+    fn read_list(it: &mut BitVectorIterator, out: &mut Vec<u64>) {
+        let list_size = it.take(32);
+        let mut prev_x = 0u64;
+        for _ in 0..list_size {
+            let x = prev_x.wrapping_add(ZigZagCode::<C>::read(it) as u64);
+            out.push(x);
+            prev_x = x;
+        }
+    }
+}
+
+/// total number of bits taken by the gaps in code C, saturating at usize::MAX
+pub fn cost<C: IntegerCode<u64>>(gaps: &[u64]) -> usize {
+    cost_by(gaps, |x| C::bits(x))
+}
+
+/// total number of bits taken by the gaps, given the length of the codeword of each one
+fn cost_by<F: Fn(u64) -> usize>(gaps: &[u64], bits: F) -> usize {
+    gaps.iter()
+        .fold(0usize, |total, &x| total.saturating_add(bits(x)))
+}
+
+/// total number of bits taken by the gaps with each available code, without encoding them.
+/// Parametric codes use the best parameter for the gaps, or a few usual ones.
+pub fn codec_costs(gaps: &[u64]) -> Vec<(String, usize)> {
+    let max_gap = gaps.iter().copied().max().unwrap_or(0);
+    let binary = DynBinaryCode { r: max_gap.max(1) };
+    let mut costs = vec![
+        ("unary".to_string(), cost::<UnaryCode>(gaps)),
+        (
+            format!("binary r={}", binary.r),
+            cost_by(gaps, |x| binary.bits(x)),
+        ),
+        ("gamma".to_string(), cost::<GammaCode>(gaps)),
+        ("delta".to_string(), cost::<DeltaCode>(gaps)),
+        ("omega".to_string(), cost::<OmegaCode>(gaps)),
+        ("levenshtein".to_string(), cost::<LevenshteinCode>(gaps)),
+        ("fibonacci".to_string(), cost::<FibonacciCode>(gaps)),
+        ("vbyte".to_string(), cost::<VByteCode>(gaps)),
+        ("nibble".to_string(), cost::<NibbleCode>(gaps)),
+    ];
+    for k in 2..=4 {
+        let code = DynZetaCode { k };
+        costs.push((format!("zeta k={}", k), cost_by(gaps, |x| code.bits(x))));
+    }
+    for k in 1..=3 {
+        let code = DynExpGolombCode { k };
+        costs.push((
+            format!("exp-golomb k={}", k),
+            cost_by(gaps, |x| code.bits(x)),
+        ));
+    }
+    let k = optimal_rice_parameter(gaps);
+    costs.push((format!("rice k={}", k), rice_cost(gaps, k)));
+    let b = optimal_golomb_parameter(gaps);
+    let code = DynGolombCode { b };
+    costs.push((format!("golomb b={}", b), cost_by(gaps, |x| code.bits(x))));
+    costs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::check_lists;
+    use super::*;
+    use crate::introduction::bit_vector::BitVector;
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    /// geometrically distributed gaps with the given mean
+    fn geometric_gaps(n: usize, mean: f64, seed: u64) -> Vec<u64> {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let p = 1.0 / (mean + 1.0);
+        (0..n)
+            .map(|_| (rng.gen::<f64>().ln() / (1.0 - p).ln()).floor() as u64)
+            .collect()
+    }
+
+    fn encode<F: FnMut(&mut BitVectorBuilder, u64)>(xs: &[u64], mut write: F) -> BitVector {
+        let mut builder = BitVectorBuilder::new();
+        for &x in xs {
+            write(&mut builder, x);
+        }
+        let mut bv = BitVector::new();
+        builder.build(&mut bv);
+        bv
+    }
+
+    #[test]
+    fn minimal_binary() {
+        for r in (0..8).chain([100, (1 << 40) + 3, u64::MAX - 1, u64::MAX]) {
+            let xs: Vec<u64> = (0..=r.min(300)).chain([r / 2, r]).collect();
+            let code = DynMinimalBinaryCode { r };
+            let bv = encode(&xs, |builder, x| code.write(builder, x));
+            let mut it = BitVectorIterator::new(&bv, 0);
+            for &x in &xs {
+                let pos = it.position();
+                assert_eq!(code.read(&mut it), x);
+                assert_eq!(it.position() - pos, minimal_binary_bits(x, r));
+            }
+        }
+
+        // with r = 5, 0 and 1 take 2 bits, and 2..=5 take 3 bits
+        let lengths: Vec<usize> = (0..=5).map(|x| minimal_binary_bits(x, 5)).collect();
+        assert_eq!(lengths, [2, 2, 3, 3, 3, 3]);
+        let xs: Vec<u64> = (0..=5).collect();
+        assert_eq!(encode(&xs, MinimalBinaryCode::<5>::write).num_bits(), 16);
+        assert_eq!(encode(&xs, BinaryCode::<5>::write).num_bits(), 18);
+
+        // when r+1 is a power of two, it takes as many bits as the binary code
+        let xs: Vec<u64> = (0..=255).collect();
+        assert_eq!(
+            encode(&xs, MinimalBinaryCode::<255>::write).num_bits(),
+            encode(&xs, BinaryCode::<255>::write).num_bits()
+        );
+    }
+
+    fn check_codewords<C: IntegerCode<u64>>(expected_bits: &[(u64, usize)]) {
+        let mut xs: Vec<u64> = (0..10_000).collect();
+        for b in 14..63 {
+            xs.extend([(1 << b) - 1, 1 << b, (1 << b) + 12345]);
+        }
+        let bv = encode(&xs, C::write);
+        let mut it = BitVectorIterator::new(&bv, 0);
+        for &x in &xs {
+            assert_eq!(C::read(&mut it), x);
+        }
+        for &(x, bits) in expected_bits {
+            assert_eq!(encode(&[x], C::write).num_bits(), bits);
+        }
+    }
+
+    #[test]
+    fn omega() {
+        // codewords of 1, 2, 4, 8, 16 and 100 in the usual 1-based definition
+        check_codewords::<OmegaCode>(&[(0, 1), (1, 3), (3, 6), (7, 7), (15, 11), (99, 13)]);
+    }
+
+    #[test]
+    fn levenshtein() {
+        check_codewords::<LevenshteinCode>(&[(0, 1), (1, 2), (2, 4), (4, 7), (8, 8), (16, 12)]);
+    }
+
+    #[test]
+    fn fibonacci() {
+        check_codewords::<FibonacciCode>(&[(0, 2), (1, 3), (2, 4), (3, 4), (4, 5), (64, 10)]);
+        // 11 = 8 + 3 is written as 0 0 1 0 1 followed by the terminating 1
+        let bv = encode(&[10], FibonacciCode::write);
+        assert_eq!(bv.get_bits(0, 6), 0b110100);
+    }
+
+    #[test]
+    fn zeta() {
+        let mut xs: Vec<u64> = (0..10_000).collect();
+        for b in 14..63 {
+            xs.extend([(1 << b) - 2, (1 << b) - 1, (1 << b) + 12345]);
+        }
+        xs.extend([u64::MAX - 1, u64::MAX]);
+
+        // zeta_1 is the gamma code
+        assert_eq!(
+            encode(&xs, ZetaCode::<1>::write).words(),
+            encode(&xs, GammaCode::write).words()
+        );
+
+        for k in 1..8 {
+            let code = DynZetaCode { k };
+            let bv = encode(&xs, |builder, x| code.write(builder, x));
+            let mut it = BitVectorIterator::new(&bv, 0);
+            for &x in &xs {
+                let pos = it.position();
+                assert_eq!(code.read(&mut it), x);
+                assert_eq!(it.position() - pos, zeta_bits(x, k));
+            }
+        }
+
+        let bv = encode(&xs, ZetaCode::<3>::write);
+        let mut it = BitVectorIterator::new(&bv, 0);
+        for &x in &xs {
+            assert_eq!(ZetaCode::<3>::read(&mut it), x);
+        }
+    }
+
+    #[test]
+    fn exp_golomb() {
+        let xs: Vec<u64> = (0..10_000).chain([1 << 40, (1 << 50) + 3]).collect();
+        for k in 0..6 {
+            let code = DynExpGolombCode { k };
+            let bv = encode(&xs, |builder, x| code.write(builder, x));
+            let mut it = BitVectorIterator::new(&bv, 0);
+            for &x in &xs {
+                assert_eq!(code.read(&mut it), x);
+            }
+            if k > 0 {
+                let rice = encode(&xs, |builder, x| write_rice(builder, x, k));
+                assert_eq!(bv.num_bits(), rice.num_bits());
+            }
+        }
+        // order 0 is the gamma code
+        assert_eq!(
+            encode(&xs, ExpGolombCode::<0>::write).words(),
+            encode(&xs, GammaCode::write).words()
+        );
+        // x = 5 with k = 1: w = 7 = 0b111, so 0 1 then 1 1
+        assert_eq!(
+            encode(&[5], ExpGolombCode::<1>::write).get_bits(0, 4),
+            0b1110
+        );
+    }
+
+    #[test]
+    fn nibble() {
+        let xs: Vec<u64> = (0..10_000).chain([1 << 40, u64::MAX]).collect();
+        let bv = encode(&xs, NibbleCode::write);
+        let mut it = BitVectorIterator::new(&bv, 0);
+        for &x in &xs {
+            assert_eq!(NibbleCode::read(&mut it), x);
+        }
+        assert_eq!(encode(&[7], NibbleCode::write).num_bits(), 4);
+        assert_eq!(encode(&[8], NibbleCode::write).num_bits(), 8);
+        assert_eq!(encode(&[u64::MAX], NibbleCode::write).num_bits(), 88);
+    }
+
+    #[test]
+    fn golomb() {
+        let xs: Vec<u64> = (0..1000).chain([12345, 1 << 20]).collect();
+        for b in [1, 2, 3, 5, 7, 10, 64, 100] {
+            let bv = encode(&xs, |builder, x| write_golomb(builder, x, b));
+            let mut it = BitVectorIterator::new(&bv, 0);
+            for &x in &xs {
+                assert_eq!(read_golomb(&mut it, b), x);
+            }
+        }
+
+        let bv = encode(&xs, GolombCode::<6>::write);
+        let mut it = BitVectorIterator::new(&bv, 0);
+        for &x in &xs {
+            assert_eq!(GolombCode::<6>::read(&mut it), x);
+        }
+    }
+
+    /// the codeword lengths given by C::bits must match the encoded ones
+    fn check_bits<C: IntegerCode<u64>>(xs: &[u64]) {
+        let bv = encode(xs, C::write);
+        let mut it = BitVectorIterator::new(&bv, 0);
+        for &x in xs {
+            let pos = it.position();
+            assert_eq!(C::read(&mut it), x);
+            assert_eq!(it.position() - pos, C::bits(x), "x = {}", x);
+        }
+    }
+
+    #[test]
+    fn codeword_bits() {
+        let mut xs: Vec<u64> = (0..2000).collect();
+        for b in 11..40 {
+            xs.extend([(1 << b) - 1, 1 << b, (1 << b) + 12345]);
+        }
+
+        check_bits::<UnaryCode>(&xs[..2000]);
+        check_bits::<BinaryCode<{ 1 << 40 }>>(&xs);
+        check_bits::<MinimalBinaryCode<{ (1 << 40) + 5 }>>(&xs);
+        check_bits::<GammaCode>(&xs);
+        check_bits::<DeltaCode>(&xs);
+        check_bits::<OmegaCode>(&xs);
+        check_bits::<LevenshteinCode>(&xs);
+        check_bits::<FibonacciCode>(&xs);
+        check_bits::<ZetaCode<3>>(&xs);
+        check_bits::<RiceCode<1>>(&xs[..1000]);
+        check_bits::<RiceCode<5>>(&xs);
+        check_bits::<ExpGolombCode<2>>(&xs);
+        check_bits::<GolombCode<7>>(&xs[..2000]);
+        check_bits::<VByteCode>(&xs);
+        check_bits::<NibbleCode>(&xs);
+        assert_eq!(VByteCode::bits(u64::MAX), 80);
+        assert_eq!(NibbleCode::bits(u64::MAX), 88);
+    }
+
+    #[test]
+    fn codec_costs_match_encoding() {
+        let gaps = geometric_gaps(10_000, 50.0, 4);
+        let costs = codec_costs(&gaps);
+        for (name, bits) in &costs {
+            println!("{}: {:.3} bits/int", name, *bits as f64 / gaps.len() as f64);
+        }
+        let find = |name: &str| costs.iter().find(|(n, _)| n == name).unwrap().1;
+        assert_eq!(find("gamma"), encode(&gaps, GammaCode::write).num_bits());
+        assert_eq!(find("vbyte"), encode(&gaps, VByteCode::write).num_bits());
+        assert_eq!(
+            find("zeta k=3"),
+            encode(&gaps, ZetaCode::<3>::write).num_bits()
+        );
+        assert_eq!(find("unary"), encode(&gaps, UnaryCode::write).num_bits());
+        assert_eq!(
+            find("exp-golomb k=2"),
+            encode(&gaps, ExpGolombCode::<2>::write).num_bits()
+        );
+        let k = optimal_rice_parameter(&gaps);
+        let rice = DynRiceCode { k };
+        let rice_bits = encode(&gaps, |builder, x| rice.write(builder, x)).num_bits();
+        assert_eq!(find(&format!("rice k={}", k)), rice_bits);
+        let max_gap = *gaps.iter().max().unwrap();
+        let binary = DynBinaryCode { r: max_gap };
+        let binary_bits = encode(&gaps, |builder, x| binary.write(builder, x)).num_bits();
+        assert_eq!(find(&format!("binary r={}", max_gap)), binary_bits);
+
+        // the largest gaps do not overflow, and only the unary cost saturates
+        let gaps = [0, 5, 1 << 63, u64::MAX, u64::MAX - 1];
+        let costs = codec_costs(&gaps);
+        let find = |name: &str| costs.iter().find(|(n, _)| n == name).unwrap().1;
+        assert_eq!(find("unary"), usize::MAX);
+        assert_eq!(find(&format!("binary r={}", u64::MAX)), 64 * gaps.len());
+        assert_eq!(find("gamma"), encode(&gaps, GammaCode::write).num_bits());
+        assert_eq!(find("omega"), encode(&gaps, OmegaCode::write).num_bits());
+        assert_eq!(
+            find("fibonacci"),
+            encode(&gaps, FibonacciCode::write).num_bits()
+        );
+        assert_eq!(
+            find("zeta k=3"),
+            encode(&gaps, ZetaCode::<3>::write).num_bits()
+        );
+        assert_eq!(
+            find("exp-golomb k=2"),
+            encode(&gaps, ExpGolombCode::<2>::write).num_bits()
+        );
+        let b = optimal_golomb_parameter(&gaps);
+        let golomb = DynGolombCode { b };
+        let golomb_bits = encode(&gaps, |builder, x| golomb.write(builder, x)).num_bits();
+        assert_eq!(find(&format!("golomb b={}", b)), golomb_bits);
+        assert_eq!(GolombCode::<1>::bits(u64::MAX), usize::MAX);
+        assert_eq!(GolombCode::<2>::bits(u64::MAX), (1 << 63) + 1);
+    }
+
+    #[test]
+    fn unary() {
+        let xs: Vec<u64> = (0..200)
+            .chain([63, 64, 65, 127, 128, 1000, 0, 5000])
+            .collect();
+        let mut builder = BitVectorBuilder::new();
+        builder.append_bits(0, 13); // unaligned start
+        for &x in &xs {
+            UnaryCode::write(&mut builder, x);
+        }
+        let mut bv = BitVector::new();
+        builder.build(&mut bv);
+        let mut it = BitVectorIterator::new(&bv, 13);
+        for &x in &xs {
+            let pos = it.position();
+            assert_eq!(<UnaryCode as IntegerCode<u64>>::read(&mut it), x);
+            assert_eq!(it.position() - pos, x as usize + 1);
+        }
+
+        // the usize and u64 variants are the same code
+        let bv = encode(&xs, |builder, x| UnaryCode::write(builder, x as usize));
+        let mut it = BitVectorIterator::new(&bv, 0);
+        for &x in &xs {
+            assert_eq!(<UnaryCode as IntegerCode<usize>>::read(&mut it), x as usize);
+        }
+        assert_eq!(bv.words(), encode(&xs, UnaryCode::write).words());
+    }
+
+    #[test]
+    fn u64_boundaries() {
+        let xs = [0, 1, (1 << 63) - 1, 1 << 63, u64::MAX - 1, u64::MAX, 0];
+        check_bits::<GammaCode>(&xs);
+        check_bits::<DeltaCode>(&xs);
+        check_bits::<OmegaCode>(&xs);
+        check_bits::<LevenshteinCode>(&xs);
+        check_bits::<FibonacciCode>(&xs);
+        check_bits::<ZetaCode<1>>(&xs);
+        check_bits::<ZetaCode<3>>(&xs);
+        check_bits::<ZetaCode<4>>(&xs);
+        check_bits::<ZetaCode<64>>(&xs);
+        check_bits::<ExpGolombCode<0>>(&xs);
+        check_bits::<ExpGolombCode<5>>(&xs);
+        check_bits::<ExpGolombCode<63>>(&xs);
+        check_bits::<RiceCode<1>>(&xs);
+        check_bits::<RiceCode<7>>(&xs);
+        check_bits::<RiceCode<63>>(&xs);
+        for k in [1, 32] {
+            let code = DynRiceCode { k };
+            let bv = encode(&xs, |builder, x| code.write(builder, x));
+            let mut it = BitVectorIterator::new(&bv, 0);
+            for &x in &xs {
+                assert_eq!(code.read(&mut it), x);
+            }
+        }
+        for k in [2, 5, 7, 32] {
+            let zeta = DynZetaCode { k };
+            let exp_golomb = DynExpGolombCode { k };
+            let bv = encode(&xs, |builder, x| {
+                zeta.write(builder, x);
+                exp_golomb.write(builder, x);
+            });
+            let mut it = BitVectorIterator::new(&bv, 0);
+            for &x in &xs {
+                let pos = it.position();
+                assert_eq!(zeta.read(&mut it), x);
+                assert_eq!(it.position() - pos, zeta.bits(x));
+                assert_eq!(exp_golomb.read(&mut it), x);
+                assert_eq!(it.position() - pos, zeta.bits(x) + exp_golomb.bits(x));
+            }
+        }
+        assert_eq!(GammaCode::bits(u64::MAX), 129);
+        assert_eq!(GammaCode::bits(1 << 63), 127);
+        assert_eq!(DeltaCode::bits(u64::MAX), GammaCode::bits(64) + 64);
+        // 2^64 is the group 1 followed by 64 zeros, after the groups of 64, 6 and 2
+        assert_eq!(OmegaCode::bits(u64::MAX), 65 + 7 + 3 + 2 + 1);
+        // 2^64 = F(93) + 6246583658587674878, so its representation has 92 bits
+        assert_eq!(FibonacciCode::bits(u64::MAX), 93);
+        // u64::MAX is alone in its interval for k = 1, and for k = 3 the interval of h = 21
+        // is capped to [2^63-1, 2^64-1], where the small offsets take 63 bits
+        assert_eq!(ZetaCode::<1>::bits(u64::MAX), GammaCode::bits(u64::MAX));
+        assert_eq!(ZetaCode::<4>::bits(u64::MAX), 17 + 64);
+        assert_eq!(ZetaCode::<3>::bits(1 << 63), 22 + 63);
+        assert_eq!(ZetaCode::<3>::bits(u64::MAX), 22 + 64);
+        assert_eq!(
+            ExpGolombCode::<0>::bits(u64::MAX),
+            GammaCode::bits(u64::MAX)
+        );
+        assert_eq!(
+            ExpGolombCode::<3>::bits(u64::MAX),
+            RiceCode::<3>::bits(u64::MAX)
+        );
+
+        // the codes of the largest values are not cut at a word boundary
+        let mut builder = BitVectorBuilder::new();
+        builder.append_bits(0, 37);
+        GammaCode::write(&mut builder, u64::MAX);
+        DeltaCode::write(&mut builder, u64::MAX);
+        RiceCode::<1>::write(&mut builder, u64::MAX);
+        OmegaCode::write(&mut builder, u64::MAX);
+        FibonacciCode::write(&mut builder, u64::MAX);
+        ZetaCode::<3>::write(&mut builder, u64::MAX);
+        ExpGolombCode::<2>::write(&mut builder, u64::MAX);
+        let mut bv = BitVector::new();
+        builder.build(&mut bv);
+        let mut it = BitVectorIterator::new(&bv, 37);
+        assert_eq!(GammaCode::read(&mut it), u64::MAX);
+        assert_eq!(DeltaCode::read(&mut it), u64::MAX);
+        assert_eq!(RiceCode::<1>::read(&mut it), u64::MAX);
+        assert_eq!(OmegaCode::read(&mut it), u64::MAX);
+        assert_eq!(FibonacciCode::read(&mut it), u64::MAX);
+        assert_eq!(ZetaCode::<3>::read(&mut it), u64::MAX);
+        assert_eq!(ExpGolombCode::<2>::read(&mut it), u64::MAX);
+        assert_eq!(it.position(), bv.num_bits());
+    }
+
+    #[test]
+    fn adaptive_rice_large_gaps() {
+        assert_eq!(optimal_rice_parameter(&[u64::MAX]), 63);
+        check_lists::<AdaptiveRiceCode>(&[
+            vec![u64::MAX],
+            vec![0, 1, u64::MAX],
+            vec![3, 1 << 63, u64::MAX],
+        ]);
+    }
+
+    #[test]
+    fn zigzag() {
+        let xs = [0, -1, 1, -2, 2, 1000, -1000, i64::MAX, i64::MIN];
+        let mapped: Vec<u64> = xs.iter().map(|&x| zigzag_encode(x)).collect();
+        assert_eq!(mapped[..5], [0, 1, 2, 3, 4]);
+        assert_eq!(mapped[7..], [u64::MAX - 1, u64::MAX]);
+        for &x in &xs {
+            assert_eq!(zigzag_decode(zigzag_encode(x)), x);
+        }
+
+        let mut builder = BitVectorBuilder::new();
+        for x in -1000..1000 {
+            ZigZagCode::<DeltaCode>::write(&mut builder, x);
+        }
+        let mut bv = BitVector::new();
+        builder.build(&mut bv);
+        let mut it = BitVectorIterator::new(&bv, 0);
+        for x in -1000..1000 {
+            let pos = it.position();
+            assert_eq!(ZigZagCode::<DeltaCode>::read(&mut it), x);
+            assert_eq!(it.position() - pos, ZigZagCode::<DeltaCode>::bits(x));
+        }
+    }
+
+    #[test]
+    fn unsorted_list() {
+        let mut rng = SmallRng::seed_from_u64(5);
+        check_lists::<UnsortedListCode<GammaCode>>(&[
+            vec![],
+            vec![5, 3, 3, 0, 1 << 40, 7],
+            vec![u64::MAX, 0, u64::MAX],
+            (0..10_000).map(|_| rng.gen_range(0..1000)).collect(),
+        ]);
+    }
+
+    #[test]
+    fn dyn_codes() {
+        let xs: Vec<u64> = (0..1000).chain([12345, 1 << 20]).collect();
+        let codes: Vec<(Box<dyn DynIntegerCode<u64>>, BitVector)> = vec![
+            (
+                Box::new(DynRiceCode { k: 3 }),
+                encode(&xs, RiceCode::<3>::write),
+            ),
+            (
+                Box::new(DynGolombCode { b: 5 }),
+                encode(&xs, GolombCode::<5>::write),
+            ),
+            (
+                Box::new(DynBinaryCode { r: 1 << 20 }),
+                encode(&xs, BinaryCode::<{ 1 << 20 }>::write),
+            ),
+        ];
+        for (code, expected) in &codes {
+            // the runtime variants must match the const generic ones bit-for-bit
+            let bv = encode(&xs, |builder, x| code.write(builder, x));
+            assert_eq!(bv.words(), expected.words());
+            let mut it = BitVectorIterator::new(&bv, 0);
+            for &x in &xs {
+                assert_eq!(code.read(&mut it), x);
+            }
+        }
+    }
+
+    #[test]
+    fn golomb_optimal_parameter() {
+        let gaps = geometric_gaps(100_000, 20.0, 3);
+        let b = optimal_golomb_parameter(&gaps);
+        println!("optimal golomb parameter b = {}", b);
+        let golomb_bits = encode(&gaps, |builder, x| write_golomb(builder, x, b)).num_bits();
+        for b_other in [b - 2, b - 1, b + 1, b + 2] {
+            let other_bits =
+                encode(&gaps, |builder, x| write_golomb(builder, x, b_other)).num_bits();
+            assert!(golomb_bits <= other_bits);
+        }
+        let rice_k3_bits = encode(&gaps, RiceCode::<3>::write).num_bits();
+        let rice_k4_bits = encode(&gaps, RiceCode::<4>::write).num_bits();
+        println!(
+            "golomb: {:.3} bits/int, rice k=3: {:.3} bits/int, rice k=4: {:.3} bits/int",
+            golomb_bits as f64 / gaps.len() as f64,
+            rice_k3_bits as f64 / gaps.len() as f64,
+            rice_k4_bits as f64 / gaps.len() as f64
+        );
+        assert!(golomb_bits < rice_k3_bits.min(rice_k4_bits));
+    }
 }
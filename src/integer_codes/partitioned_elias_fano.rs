@@ -0,0 +1,608 @@
+use super::bit_vector_iterator::BitVectorIterator;
+use super::elias_fano::{low_mask, num_low_bits, EliasFano};
+use super::{GammaCode, IntegerCode, ListCode, UnaryCode};
+use crate::introduction::bit_vector::{BitVector, BitVectorBuilder};
+use crate::introduction::util::select_in_word;
+use bincode::{deserialize_from, serialize_into};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+
+/// cost of a chunk in the upper level, in bits
+const FIX_COST: u64 = 64;
+/// approximation parameters of the optimal partitioning
+const EPS1: f64 = 0.03;
+const EPS2: f64 = 0.3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ChunkKind {
+    /// all values of the universe are present, nothing is stored
+    Range,
+    /// one bit per value of the universe
+    Bitmap,
+    /// plain Elias-Fano, without select directories
+    EliasFano,
+}
+
+/// cheapest encoding of a chunk of n strictly increasing values in [0, max],
+/// it only depends on max and n so it does not need to be stored
+fn chunk_kind(max: u64, n: usize) -> (ChunkKind, u64) {
+    if max == n as u64 - 1 {
+        return (ChunkKind::Range, 0);
+    }
+    let l = num_low_bits(n, max);
+    let ef_cost = (n * l + n) as u64 + (max >> l);
+    if max < ef_cost {
+        (ChunkKind::Bitmap, max + 1)
+    } else {
+        (ChunkKind::EliasFano, ef_cost)
+    }
+}
+
+fn chunk_cost(max: u64, n: usize) -> u64 {
+    FIX_COST + chunk_kind(max, n).1
+}
+
+/// a window [start, end) over the values, whose universe starts after the value before it
+struct CostWindow {
+    start: usize,
+    end: usize,
+    min_value: u64,
+    max_value: u64,
+    cost_upper_bound: u64,
+}
+
+impl CostWindow {
+    /// largest value of the window relative to its universe
+    fn max(&self) -> u64 {
+        self.max_value - self.min_value
+    }
+
+    fn size(&self) -> usize {
+        self.end - self.start
+    }
+}
+
+/// ends of the chunks minimizing the total cost, up to a factor (1 + EPS1) (1 + EPS2)
+///
+/// this is the algorithm of Ottaviano and Venturini (2014): instead of trying all O(n^2)
+/// chunks, it only keeps one sliding window per cost class of the form FIX_COST (1 + EPS2)^k,
+/// extending each window as long as its cost stays below the bound of its class
+fn optimal_partition(values: &[u64]) -> Vec<usize> {
+    let n = values.len();
+    let single_chunk_cost = chunk_cost(values[n - 1], n);
+    let mut min_cost = vec![single_chunk_cost; n + 1];
+    min_cost[0] = 0;
+    let mut path = vec![0; n + 1];
+
+    let cost_lower_bound = chunk_cost(0, 1);
+    let mut cost_bound = cost_lower_bound;
+    let mut windows = Vec::new();
+    while (cost_bound as f64) < cost_lower_bound as f64 / EPS1 {
+        windows.push(CostWindow {
+            start: 0,
+            end: 0,
+            min_value: 0,
+            max_value: 0,
+            cost_upper_bound: cost_bound,
+        });
+        if cost_bound >= single_chunk_cost {
+            break;
+        }
+        cost_bound = (cost_bound as f64 * (1.0 + EPS2)) as u64;
+    }
+
+    for i in 0..n {
+        let mut last_end = i + 1;
+        for window in &mut windows {
+            debug_assert_eq!(window.start, i);
+            window.min_value = if i == 0 { 0 } else { values[i - 1] + 1 };
+            while window.end < last_end {
+                window.max_value = values[window.end];
+                window.end += 1;
+            }
+            loop {
+                let window_cost = chunk_cost(window.max(), window.size());
+                if min_cost[i] + window_cost < min_cost[window.end] {
+                    min_cost[window.end] = min_cost[i] + window_cost;
+                    path[window.end] = i;
+                }
+                last_end = window.end;
+                if window.end == n || window_cost >= window.cost_upper_bound {
+                    break;
+                }
+                window.max_value = values[window.end];
+                window.end += 1;
+            }
+            window.start += 1;
+        }
+    }
+
+    let mut ends = Vec::new();
+    let mut end = n;
+    while end != 0 {
+        ends.push(end);
+        end = path[end];
+    }
+    ends.reverse();
+    ends
+}
+
+/// write the values of a chunk in [base, base + max]
+fn write_chunk(builder: &mut BitVectorBuilder, chunk: &[u64], base: u64, max: u64) {
+    match chunk_kind(max, chunk.len()).0 {
+        ChunkKind::Range => {}
+        ChunkKind::Bitmap => {
+            let mut next = 0;
+            for &v in chunk {
+                UnaryCode::write(builder, v - base - next);
+                next = v - base + 1;
+            }
+        }
+        ChunkKind::EliasFano => {
+            let l = num_low_bits(chunk.len(), max);
+            for &v in chunk {
+                builder.append_bits((v - base) & low_mask(l), l);
+            }
+            let mut prev_high = 0;
+            for &v in chunk {
+                let high = (v - base) >> l;
+                UnaryCode::write(builder, high - prev_high);
+                prev_high = high;
+            }
+        }
+    }
+}
+
+/// decode the n values of a chunk in [base, base + max] and append them to out
+fn read_chunk(it: &mut BitVectorIterator, n: usize, base: u64, max: u64, out: &mut Vec<u64>) {
+    match chunk_kind(max, n).0 {
+        ChunkKind::Range => out.extend((0..n as u64).map(|j| base + j)),
+        ChunkKind::Bitmap => {
+            let mut next = 0;
+            for _ in 0..n {
+                let d = next + it.skip_zeros() as u64;
+                out.push(base + d);
+                next = d + 1;
+            }
+        }
+        ChunkKind::EliasFano => {
+            let l = num_low_bits(n, max);
+            let start = out.len();
+            for _ in 0..n {
+                out.push(it.take(l));
+            }
+            let mut high = 0;
+            for low in &mut out[start..] {
+                high += it.skip_zeros() as u64;
+                *low = base + ((high << l) | *low);
+            }
+        }
+    }
+}
+
+/// position of the k-th one at or after pos, assuming it exists
+fn select_from(bv: &BitVector, mut pos: usize, mut k: usize) -> usize {
+    loop {
+        let word = bv.get_word64(pos);
+        let popcount = word.count_ones() as usize;
+        if k < popcount {
+            return pos + select_in_word(word, k);
+        }
+        k -= popcount;
+        pos += 64;
+    }
+}
+
+/// position of the k-th zero at or after pos, assuming it exists
+fn select0_from(bv: &BitVector, mut pos: usize, mut k: usize) -> usize {
+    loop {
+        let word = !bv.get_word64(pos);
+        let popcount = word.count_ones() as usize;
+        if k < popcount {
+            return pos + select_in_word(word, k);
+        }
+        k -= popcount;
+        pos += 64;
+    }
+}
+
+/// number of ones in [begin, end)
+fn count_ones(bv: &BitVector, mut begin: usize, end: usize) -> usize {
+    let mut count = 0;
+    while begin + 64 <= end {
+        count += bv.get_word64(begin).count_ones() as usize;
+        begin += 64;
+    }
+    count + bv.get_bits(begin, end - begin).count_ones() as usize
+}
+
+/// Partitioned Elias-Fano representation of a strictly increasing sequence of integers.
+///
+/// The sequence is split into chunks by `optimal_partition`, each chunk being encoded
+/// relatively to the value following the previous chunk, with its cheapest `ChunkKind`.
+/// The upper level stores the last value, the end position and the bit offset of every chunk
+/// as Elias-Fano sequences, so that chunks are located in O(1) and then decoded locally.
+#[derive(Default, Serialize, Deserialize)]
+pub struct PartitionedEliasFano {
+    num_values: usize,
+    endpoints: EliasFano,
+    sizes: EliasFano,
+    offsets: EliasFano,
+    chunks: BitVector,
+}
+
+impl PartitionedEliasFano {
+    pub fn new() -> Self {
+        Self {
+            num_values: 0,
+            endpoints: EliasFano::new(),
+            sizes: EliasFano::new(),
+            offsets: EliasFano::new(),
+            chunks: BitVector::new(),
+        }
+    }
+
+    pub fn build(&mut self, values: &[u64]) {
+        assert!(
+            values.windows(2).all(|w| w[0] < w[1]),
+            "the values must be strictly increasing"
+        );
+        *self = Self::new();
+        self.num_values = values.len();
+        if values.is_empty() {
+            return;
+        }
+
+        let ends = optimal_partition(values);
+        let mut builder = BitVectorBuilder::new();
+        let mut endpoints = Vec::with_capacity(ends.len());
+        let mut offsets = Vec::with_capacity(ends.len());
+        let (mut begin, mut base) = (0, 0);
+        for &end in &ends {
+            let last = values[end - 1];
+            offsets.push(builder.num_bits() as u64);
+            write_chunk(&mut builder, &values[begin..end], base, last - base);
+            endpoints.push(last);
+            begin = end;
+            // wraps only after the chunk of u64::MAX, which is the last one
+            base = last.wrapping_add(1);
+        }
+
+        let sizes: Vec<u64> = ends.iter().map(|&end| end as u64).collect();
+        self.endpoints.build(&endpoints);
+        self.sizes.build(&sizes);
+        self.offsets.build(&offsets);
+        builder.build(&mut self.chunks);
+    }
+
+    pub fn len(&self) -> usize {
+        self.num_values
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.num_values == 0
+    }
+
+    pub fn num_chunks(&self) -> usize {
+        self.endpoints.len()
+    }
+
+    /// total number of bits, including the upper level
+    pub fn num_bits(&self) -> usize {
+        self.endpoints.num_bits()
+            + self.sizes.num_bits()
+            + self.offsets.num_bits()
+            + self.chunks.num_bits()
+    }
+
+    /// first index, number of values, base and largest value minus base of the chunk c
+    fn chunk_bounds(&self, c: usize) -> (usize, usize, u64, u64) {
+        let (begin, base) = if c == 0 {
+            (0, 0)
+        } else {
+            (
+                self.sizes.access(c - 1) as usize,
+                self.endpoints.access(c - 1) + 1,
+            )
+        };
+        let end = self.sizes.access(c) as usize;
+        let last = self.endpoints.access(c);
+        (begin, end - begin, base, last - base)
+    }
+
+    fn decode_chunk(&self, c: usize, out: &mut Vec<u64>) {
+        let (_, n, base, max) = self.chunk_bounds(c);
+        let offset = self.offsets.access(c) as usize;
+        let mut it = BitVectorIterator::new(&self.chunks, offset);
+        read_chunk(&mut it, n, base, max, out);
+    }
+
+    /// return the i-th value
+    pub fn access(&self, i: usize) -> u64 {
+        debug_assert!(i < self.num_values);
+        let (c, _) = self.sizes.next_geq(i as u64 + 1).unwrap();
+        let (begin, n, base, max) = self.chunk_bounds(c);
+        let offset = self.offsets.access(c) as usize;
+        let j = i - begin;
+        match chunk_kind(max, n).0 {
+            ChunkKind::Range => base + j as u64,
+            ChunkKind::Bitmap => base + (select_from(&self.chunks, offset, j) - offset) as u64,
+            ChunkKind::EliasFano => {
+                let l = num_low_bits(n, max);
+                let low = self.chunks.get_bits(offset + j * l, l);
+                let upper = offset + n * l;
+                let high = (select_from(&self.chunks, upper, j) - upper - j) as u64;
+                base + ((high << l) | low)
+            }
+        }
+    }
+
+    /// return the position and value of the first value >= x, if any
+    pub fn next_geq(&self, x: u64) -> Option<(usize, u64)> {
+        let (c, _) = self.endpoints.next_geq(x)?;
+        let (begin, n, base, max) = self.chunk_bounds(c);
+        let offset = self.offsets.access(c) as usize;
+        // the values before the chunk are smaller than x, so x >= base
+        let d = x - base;
+        let (j, v) = match chunk_kind(max, n).0 {
+            ChunkKind::Range => (d as usize, x),
+            ChunkKind::Bitmap => {
+                let pos = select_from(&self.chunks, offset + d as usize, 0);
+                let j = count_ones(&self.chunks, offset, pos);
+                (j, base + (pos - offset) as u64)
+            }
+            ChunkKind::EliasFano => {
+                // skip to the bucket of the high part of d, then scan it:
+                // the last value of the chunk is >= x, so the scan stops within the chunk
+                let l = num_low_bits(n, max);
+                let upper = offset + n * l;
+                let high = (d >> l) as usize;
+                let mut pos = if high == 0 {
+                    upper
+                } else {
+                    select0_from(&self.chunks, upper, high - 1) + 1
+                };
+                let mut j = pos - upper - high;
+                loop {
+                    pos = select_from(&self.chunks, pos, 0);
+                    let low = self.chunks.get_bits(offset + j * l, l);
+                    let v = base + ((((pos - upper - j) as u64) << l) | low);
+                    if v >= x {
+                        break (j, v);
+                    }
+                    j += 1;
+                    pos += 1;
+                }
+            }
+        };
+        Some((begin + j, v))
+    }
+
+    pub fn iter(&self) -> PartitionedEliasFanoIterator<'_> {
+        PartitionedEliasFanoIterator {
+            pef: self,
+            chunk: 0,
+            buf: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    /// append the strictly increasing values to the builder: the number of chunks
+    /// in gamma code, their endpoints and sizes with `EliasFano::encode`, then the chunks
+    pub fn encode(builder: &mut BitVectorBuilder, values: &[u64]) {
+        assert!(
+            values.windows(2).all(|w| w[0] < w[1]),
+            "the values must be strictly increasing"
+        );
+        if values.is_empty() {
+            return;
+        }
+        let ends = optimal_partition(values);
+        let endpoints: Vec<u64> = ends.iter().map(|&end| values[end - 1]).collect();
+        let sizes: Vec<u64> = ends.iter().map(|&end| end as u64).collect();
+        GammaCode::write(builder, ends.len() as u64 - 1);
+        EliasFano::encode(builder, &endpoints);
+        EliasFano::encode(builder, &sizes);
+
+        let (mut begin, mut base) = (0, 0);
+        for (&end, &last) in ends.iter().zip(&endpoints) {
+            write_chunk(builder, &values[begin..end], base, last - base);
+            begin = end;
+            base = last.wrapping_add(1);
+        }
+    }
+
+    /// decode n values written by `encode` and append them to out
+    pub fn decode(it: &mut BitVectorIterator, n: usize, out: &mut Vec<u64>) {
+        if n == 0 {
+            return;
+        }
+        let num_chunks = GammaCode::read(it) as usize + 1;
+        let mut upper = Vec::with_capacity(2 * num_chunks);
+        EliasFano::decode(it, num_chunks, &mut upper);
+        EliasFano::decode(it, num_chunks, &mut upper);
+        let (endpoints, sizes) = upper.split_at(num_chunks);
+
+        let (mut begin, mut base) = (0, 0);
+        for (&end, &last) in sizes.iter().zip(endpoints) {
+            let end = end as usize;
+            read_chunk(it, end - begin, base, last - base, out);
+            begin = end;
+            base = last.wrapping_add(1);
+        }
+    }
+
+    pub fn save<W: Write>(&self, writer: W) {
+        serialize_into(writer, &self).unwrap();
+    }
+
+    pub fn load<R: Read>(&mut self, reader: R) {
+        *self = deserialize_from(reader).unwrap();
+    }
+}
+
+impl ListCode for PartitionedEliasFano {
+    /// the 32-bit size of the list followed by `PartitionedEliasFano::encode`,
+    /// which panics if the list is not strictly increasing
+    fn write_list(builder: &mut BitVectorBuilder, list: &[u64]) {
+        builder.append_bits(list.len() as u64, 32);
+        Self::encode(builder, list);
+    }
+
+    fn read_list(it: &mut BitVectorIterator, out: &mut Vec<u64>) {
+        let list_size = it.take(32) as usize;
+        Self::decode(it, list_size, out);
+    }
+}
+
+/// iterate over the values, decoding one chunk at a time
+pub struct PartitionedEliasFanoIterator<'a> {
+    pef: &'a PartitionedEliasFano,
+    chunk: usize,
+    buf: Vec<u64>,
+    pos: usize,
+}
+
+impl Iterator for PartitionedEliasFanoIterator<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.pos == self.buf.len() {
+            if self.chunk == self.pef.num_chunks() {
+                return None;
+            }
+            self.buf.clear();
+            self.pef.decode_chunk(self.chunk, &mut self.buf);
+            self.chunk += 1;
+            self.pos = 0;
+        }
+        self.pos += 1;
+        Some(self.buf[self.pos - 1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    fn random_clustered(n: usize, seed: u64) -> Vec<u64> {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let mut values = Vec::with_capacity(n);
+        let mut x = 0;
+        while values.len() < n {
+            // alternate dense runs, sparse runs and large jumps
+            match rng.gen_range(0..3) {
+                0 => {
+                    for _ in 0..rng.gen_range(1..500) {
+                        x += 1;
+                        values.push(x);
+                    }
+                }
+                1 => {
+                    for _ in 0..rng.gen_range(1..500) {
+                        x += rng.gen_range(1..4);
+                        values.push(x);
+                    }
+                }
+                _ => {
+                    x += rng.gen_range(1..1_000_000);
+                    values.push(x);
+                }
+            }
+        }
+        values.truncate(n);
+        values
+    }
+
+    #[test]
+    fn partitioned_elias_fano() {
+        let lists = [
+            vec![],
+            vec![0],
+            vec![0, 1, 2, 3],
+            vec![3, 1 << 40],
+            vec![u64::MAX],
+            vec![0, 1, u64::MAX - 2, u64::MAX - 1, u64::MAX],
+            (u64::MAX - 999..=u64::MAX).collect(),
+            (0..10_000).map(|x| 3 * x).collect(),
+            random_clustered(20_000, 1),
+        ];
+        for values in &lists {
+            let mut pef = PartitionedEliasFano::new();
+            pef.build(values);
+            assert_eq!(pef.len(), values.len());
+            assert_eq!(pef.iter().collect::<Vec<_>>(), *values);
+            for (i, &v) in values.iter().enumerate() {
+                assert_eq!(pef.access(i), v);
+            }
+            for &v in values {
+                for x in [v.saturating_sub(1), v, v.saturating_add(1)] {
+                    let expected = values.iter().position(|&v| v >= x).map(|i| (i, values[i]));
+                    assert_eq!(pef.next_geq(x), expected);
+                }
+            }
+
+            let mut builder = BitVectorBuilder::new();
+            PartitionedEliasFano::encode(&mut builder, values);
+            let mut bv = BitVector::new();
+            builder.build(&mut bv);
+            let mut decoded = Vec::new();
+            PartitionedEliasFano::decode(
+                &mut BitVectorIterator::new(&bv, 0),
+                values.len(),
+                &mut decoded,
+            );
+            assert_eq!(decoded, *values);
+        }
+    }
+
+    #[test]
+    fn partitioned_elias_fano_next_geq() {
+        // a dense run then sparse values, to also get range chunks
+        let dense_then_sparse = (0..5000).chain((1..5000).map(|x| 5000 + 1000 * x));
+        let mut rng = SmallRng::seed_from_u64(4);
+        for values in [random_clustered(50_000, 3), dense_then_sparse.collect()] {
+            let mut pef = PartitionedEliasFano::new();
+            pef.build(&values);
+            let last = *values.last().unwrap();
+            for _ in 0..100_000 {
+                let x = rng.gen_range(0..=last + 1);
+                let i = values.partition_point(|&v| v < x);
+                assert_eq!(pef.next_geq(x), values.get(i).map(|&v| (i, v)));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "strictly increasing")]
+    fn partitioned_elias_fano_duplicates() {
+        PartitionedEliasFano::new().build(&[1, 2, 2, 3]);
+    }
+
+    #[test]
+    fn partitioned_elias_fano_space() {
+        let values = random_clustered(100_000, 2);
+        let mut pef = PartitionedEliasFano::new();
+        pef.build(&values);
+        let mut ef = EliasFano::new();
+        ef.build(&values);
+        println!(
+            "partitioned_elias_fano: {} chunks, {:.2} bits/int",
+            pef.num_chunks(),
+            pef.num_bits() as f64 / values.len() as f64
+        );
+        println!(
+            "elias_fano: {:.2} bits/int",
+            ef.num_bits() as f64 / values.len() as f64
+        );
+        assert!(pef.num_bits() < ef.num_bits());
+
+        let mut buf = Vec::new();
+        pef.save(&mut buf);
+        let mut loaded = PartitionedEliasFano::new();
+        loaded.load(&buf[..]);
+        assert_eq!(loaded.iter().collect::<Vec<_>>(), values);
+    }
+}
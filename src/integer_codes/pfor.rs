@@ -0,0 +1,323 @@
+use super::bit_vector_iterator::BitVectorIterator;
+use super::{gaps, push_prefix_sums, GammaCode, IntegerCode, ListCode};
+use crate::introduction::bit_vector::BitVectorBuilder;
+use crate::introduction::util::msb;
+use num_traits::int::PrimInt;
+
+/// number of gaps per block, the last block of a list may be shorter
+pub const BLOCK_SIZE: usize = 128;
+
+/// fraction of the values of a block that PFor and NewPFD fit in b bits
+const FIT_RATIO: f64 = 0.9;
+
+/// how the bit width b is chosen and how the exceptions, the values that do not fit
+/// in b bits, are stored
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Variant {
+    /// PFor (Zukowski et al., 2006): b fits 90% of the values, the exceptions are
+    /// written verbatim on the width of the largest value, with their positions on 7 bits
+    PFor,
+    /// NewPFD (Yan et al., 2009): b fits 90% of the values, the slots of the exceptions
+    /// keep their low b bits, and their high parts and positions are coded separately
+    NewPfd,
+    /// OptPFD (Yan et al., 2009): the format of NewPFD with the b minimizing the block size
+    OptPfd,
+}
+
+/// number of bits of x, i.e., the smallest b such that x < 2^b
+fn width(x: u64) -> usize {
+    if x == 0 {
+        0
+    } else {
+        msb(x) + 1
+    }
+}
+
+fn fits(x: u64, b: usize) -> bool {
+    width(x) <= b
+}
+
+/// number of bits taken by the block when packed on b bits
+pub fn block_cost(block: &[u64], b: usize, variant: Variant) -> usize {
+    let header = 7 + 8; // b, then the number of exceptions
+    let slots = block.len() * b;
+    let num_exceptions = block.iter().filter(|&&x| !fits(x, b)).count();
+    let exceptions = match variant {
+        Variant::PFor => {
+            if num_exceptions == 0 {
+                0
+            } else {
+                let w = block.iter().map(|&x| width(x)).max().unwrap();
+                7 + num_exceptions * (7 + w)
+            }
+        }
+        Variant::NewPfd | Variant::OptPfd => {
+            let mut bits = 0;
+            let mut prev_pos = 0;
+            for (pos, &x) in block.iter().enumerate() {
+                if !fits(x, b) {
+                    bits += GammaCode::bits((pos - prev_pos) as u64);
+                    bits += GammaCode::bits((x >> b) - 1);
+                    prev_pos = pos;
+                }
+            }
+            bits
+        }
+    };
+    header + slots + exceptions
+}
+
+/// bit width b used to pack the block
+pub fn choose_width(block: &[u64], variant: Variant) -> usize {
+    if block.is_empty() {
+        return 0;
+    }
+    match variant {
+        Variant::PFor | Variant::NewPfd => {
+            let mut widths: Vec<usize> = block.iter().map(|&x| width(x)).collect();
+            widths.sort_unstable();
+            let num_fitting = (FIT_RATIO * block.len() as f64).ceil() as usize;
+            widths[num_fitting.max(1) - 1]
+        }
+        Variant::OptPfd => (0..=64)
+            .min_by_key(|&b| block_cost(block, b, variant))
+            .unwrap(),
+    }
+}
+
+/// append the block of at most BLOCK_SIZE values to the builder:
+/// b on 7 bits, the number of exceptions on 8 bits, the slots on b bits, then the exceptions
+pub fn encode_block(builder: &mut BitVectorBuilder, block: &[u64], variant: Variant) {
+    debug_assert!(block.len() <= BLOCK_SIZE);
+    let b = choose_width(block, variant);
+    let exceptions: Vec<usize> = (0..block.len()).filter(|&i| !fits(block[i], b)).collect();
+    builder.append_bits(b as u64, 7);
+    builder.append_bits(exceptions.len() as u64, 8);
+
+    let low_mask = 1u64.checked_shl(b as u32).unwrap_or(0).wrapping_sub(1);
+    for &x in block {
+        let slot = if variant == Variant::PFor && !fits(x, b) {
+            0
+        } else {
+            x & low_mask
+        };
+        builder.append_bits(slot, b);
+    }
+
+    if exceptions.is_empty() {
+        return;
+    }
+    match variant {
+        Variant::PFor => {
+            let w = block.iter().map(|&x| width(x)).max().unwrap();
+            builder.append_bits(w as u64, 7);
+            for &pos in &exceptions {
+                builder.append_bits(pos as u64, 7);
+                builder.append_bits(block[pos], w);
+            }
+        }
+        Variant::NewPfd | Variant::OptPfd => {
+            let mut prev_pos = 0;
+            for &pos in &exceptions {
+                GammaCode::write(builder, (pos - prev_pos) as u64);
+                GammaCode::write(builder, (block[pos] >> b) - 1);
+                prev_pos = pos;
+            }
+        }
+    }
+}
+
+/// decode a block of out.len() values written by `encode_block` into out
+pub fn decode_block<T: PrimInt>(it: &mut BitVectorIterator, variant: Variant, out: &mut [T]) {
+    let b = it.take(7) as usize;
+    let num_exceptions = it.take(8) as usize;
+    for v in out.iter_mut() {
+        *v = T::from(it.take(b)).unwrap();
+    }
+    if num_exceptions == 0 {
+        return;
+    }
+    match variant {
+        Variant::PFor => {
+            let w = it.take(7) as usize;
+            for _ in 0..num_exceptions {
+                let pos = it.take(7) as usize;
+                out[pos] = T::from(it.take(w)).unwrap();
+            }
+        }
+        Variant::NewPfd | Variant::OptPfd => {
+            let mut pos = 0;
+            for _ in 0..num_exceptions {
+                pos += GammaCode::read(it) as usize;
+                let high = GammaCode::read(it) + 1;
+                out[pos] = out[pos] | T::from(high << b).unwrap();
+            }
+        }
+    }
+}
+
+/// the 32-bit size of the list, then its gaps in blocks of BLOCK_SIZE
+fn write_list(builder: &mut BitVectorBuilder, list: &[u64], variant: Variant) {
+    builder.append_bits(list.len() as u64, 32);
+    for block in gaps(list).chunks(BLOCK_SIZE) {
+        encode_block(builder, block, variant);
+    }
+}
+
+fn read_list(it: &mut BitVectorIterator, out: &mut Vec<u64>, variant: Variant) {
+    let list_size = it.take(32) as usize;
+    let mut buf = [0u64; BLOCK_SIZE];
+    let mut prev_x = 0;
+    let mut remaining = list_size;
+    while remaining > 0 {
+        let n = remaining.min(BLOCK_SIZE);
+        decode_block(it, variant, &mut buf[..n]);
+        push_prefix_sums(out, &mut prev_x, &buf[..n]);
+        remaining -= n;
+    }
+}
+
+pub struct PForCode;
+
+impl ListCode for PForCode {
+    fn write_list(builder: &mut BitVectorBuilder, list: &[u64]) {
+        write_list(builder, list, Variant::PFor);
+    }
+
+    fn read_list(it: &mut BitVectorIterator, out: &mut Vec<u64>) {
+        read_list(it, out, Variant::PFor);
+    }
+}
+
+pub struct NewPfdCode;
+
+impl ListCode for NewPfdCode {
+    fn write_list(builder: &mut BitVectorBuilder, list: &[u64]) {
+        write_list(builder, list, Variant::NewPfd);
+    }
+
+    fn read_list(it: &mut BitVectorIterator, out: &mut Vec<u64>) {
+        read_list(it, out, Variant::NewPfd);
+    }
+}
+
+pub struct OptPfdCode;
+
+impl ListCode for OptPfdCode {
+    fn write_list(builder: &mut BitVectorBuilder, list: &[u64]) {
+        write_list(builder, list, Variant::OptPfd);
+    }
+
+    fn read_list(it: &mut BitVectorIterator, out: &mut Vec<u64>) {
+        read_list(it, out, Variant::OptPfd);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::introduction::bit_vector::BitVector;
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    const VARIANTS: [Variant; 3] = [Variant::PFor, Variant::NewPfd, Variant::OptPfd];
+
+    /// small gaps with a few large outliers
+    fn random_blocks(seed: u64) -> Vec<Vec<u64>> {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let mut blocks = vec![
+            vec![0; BLOCK_SIZE],
+            vec![7],
+            vec![u64::MAX; 3],
+            (0..BLOCK_SIZE as u64).collect(),
+        ];
+        for _ in 0..50 {
+            let n = rng.gen_range(1..=BLOCK_SIZE);
+            let block = (0..n)
+                .map(|_| {
+                    if rng.gen_bool(0.05) {
+                        rng.gen_range(0..1 << 30)
+                    } else {
+                        rng.gen_range(0..20)
+                    }
+                })
+                .collect();
+            blocks.push(block);
+        }
+        blocks
+    }
+
+    #[test]
+    fn pfor_blocks() {
+        let blocks = random_blocks(1);
+        for variant in VARIANTS {
+            let mut builder = BitVectorBuilder::new();
+            for block in &blocks {
+                let start = builder.num_bits();
+                encode_block(&mut builder, block, variant);
+                let b = choose_width(block, variant);
+                assert_eq!(builder.num_bits() - start, block_cost(block, b, variant));
+            }
+            let mut bv = BitVector::new();
+            builder.build(&mut bv);
+
+            let mut it = BitVectorIterator::new(&bv, 0);
+            for block in &blocks {
+                let mut out = vec![0u64; block.len()];
+                decode_block(&mut it, variant, &mut out);
+                assert_eq!(out, *block);
+            }
+        }
+    }
+
+    #[test]
+    fn pfor_u32_buffer() {
+        let blocks: Vec<Vec<u64>> = random_blocks(2)
+            .into_iter()
+            .filter(|block| block.iter().all(|&x| x <= u32::MAX as u64))
+            .collect();
+        let mut builder = BitVectorBuilder::new();
+        for block in &blocks {
+            encode_block(&mut builder, block, Variant::NewPfd);
+        }
+        let mut bv = BitVector::new();
+        builder.build(&mut bv);
+
+        let mut it = BitVectorIterator::new(&bv, 0);
+        let mut out = [0u32; BLOCK_SIZE];
+        for block in &blocks {
+            decode_block(&mut it, Variant::NewPfd, &mut out[..block.len()]);
+            assert!(out.iter().zip(block).all(|(&v, &x)| v as u64 == x));
+        }
+    }
+
+    #[test]
+    fn pfor_optimal_width() {
+        let blocks = random_blocks(3);
+        let cost = |variant| -> usize {
+            blocks
+                .iter()
+                .map(|block| block_cost(block, choose_width(block, variant), variant))
+                .sum()
+        };
+        let (pfor, new_pfd, opt_pfd) = (
+            cost(Variant::PFor),
+            cost(Variant::NewPfd),
+            cost(Variant::OptPfd),
+        );
+        println!(
+            "pfor: {} bits, newpfd: {} bits, optpfd: {} bits",
+            pfor, new_pfd, opt_pfd
+        );
+        assert!(opt_pfd <= new_pfd);
+        for block in &blocks {
+            let b = choose_width(block, Variant::OptPfd);
+            for other_b in 0..=64 {
+                assert!(
+                    block_cost(block, b, Variant::OptPfd)
+                        <= block_cost(block, other_b, Variant::OptPfd)
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,303 @@
+use super::bit_vector_iterator::BitVectorIterator;
+use super::{gaps, push_prefix_sums, ListCode};
+use crate::introduction::bit_vector::BitVectorBuilder;
+
+/// a word-aligned code packing as many gaps as possible in each word: a 4-bit selector
+/// in the low bits of the word chooses a layout, i.e., runs of (count, width) slots
+/// filling the other bits. The last word of a list may be padded with zeros.
+///
+/// The escape layout holds a single gap on all the data bits: when these bits are all
+/// ones, the word is an escape word and the gap follows in full on the next 64 bits.
+/// Gaps that do not fit the largest slot, or that equal the escape value, are escaped.
+struct Simple {
+    word_bits: usize,
+    layouts: [&'static [(usize, usize)]; 16],
+    escape: usize,
+}
+
+/// Simple9 (Anh and Moffat, 2005): 32-bit words with 9 layouts of equal widths
+const SIMPLE9: Simple = Simple {
+    word_bits: 32,
+    layouts: [
+        &[(28, 1)],
+        &[(14, 2)],
+        &[(9, 3)],
+        &[(7, 4)],
+        &[(5, 5)],
+        &[(4, 7)],
+        &[(3, 9)],
+        &[(2, 14)],
+        &[(1, 28)],
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+    ],
+    escape: 8,
+};
+
+/// Simple16 (Zhang et al., 2008): 32-bit words with 16 layouts, some of them mixing widths
+const SIMPLE16: Simple = Simple {
+    word_bits: 32,
+    layouts: [
+        &[(28, 1)],
+        &[(7, 2), (14, 1)],
+        &[(7, 1), (7, 2), (7, 1)],
+        &[(14, 1), (7, 2)],
+        &[(14, 2)],
+        &[(1, 4), (8, 3)],
+        &[(1, 3), (4, 4), (3, 3)],
+        &[(7, 4)],
+        &[(4, 5), (2, 4)],
+        &[(2, 4), (4, 5)],
+        &[(3, 6), (2, 5)],
+        &[(2, 5), (3, 6)],
+        &[(4, 7)],
+        &[(1, 10), (2, 9)],
+        &[(2, 14)],
+        &[(1, 28)],
+    ],
+    escape: 15,
+};
+
+/// Simple8b (Anh and Moffat, 2010): 64-bit words with 60 data bits,
+/// the first two layouts code runs of 240 and 120 zeros
+const SIMPLE8B: Simple = Simple {
+    word_bits: 64,
+    layouts: [
+        &[(240, 0)],
+        &[(120, 0)],
+        &[(60, 1)],
+        &[(30, 2)],
+        &[(20, 3)],
+        &[(15, 4)],
+        &[(12, 5)],
+        &[(10, 6)],
+        &[(8, 7)],
+        &[(7, 8)],
+        &[(6, 10)],
+        &[(5, 12)],
+        &[(4, 15)],
+        &[(3, 20)],
+        &[(2, 30)],
+        &[(1, 60)],
+    ],
+    escape: 15,
+};
+
+impl Simple {
+    /// number of gaps packed with the layout, or None if one of them does not fit
+    fn fit(layout: &[(usize, usize)], gaps: &[u64]) -> Option<usize> {
+        let mut i = 0;
+        for &(count, width) in layout {
+            for &x in gaps[i..].iter().take(count) {
+                if x >> width != 0 {
+                    return None;
+                }
+            }
+            i = (i + count).min(gaps.len());
+        }
+        Some(i)
+    }
+
+    /// the data bits of an escape word, larger than any gap of the escape layout
+    fn escape_value(&self) -> u64 {
+        (1 << (self.word_bits - 4)) - 1
+    }
+
+    fn padding(&self, pos: usize) -> usize {
+        (self.word_bits - pos % self.word_bits) % self.word_bits
+    }
+
+    /// the 32-bit size of the list, then the words from the next word boundary
+    fn write_list(&self, builder: &mut BitVectorBuilder, list: &[u64]) {
+        builder.append_bits(list.len() as u64, 32);
+        builder.append_bits(0, self.padding(builder.num_bits()));
+
+        let gaps = gaps(list);
+
+        let mut i = 0;
+        while i < gaps.len() {
+            // the escape layout fits any gap below the escape value
+            let found = if gaps[i] < self.escape_value() {
+                self.layouts
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, layout)| !layout.is_empty())
+                    .find_map(|(selector, layout)| Some((selector, Self::fit(layout, &gaps[i..])?)))
+            } else {
+                None
+            };
+            let Some((selector, n)) = found else {
+                let word = self.escape as u64 | self.escape_value() << 4;
+                builder.append_bits(word, self.word_bits);
+                builder.append_bits(gaps[i], 64);
+                i += 1;
+                continue;
+            };
+            let mut word = selector as u64;
+            let mut shift = 4;
+            let mut j = i;
+            for &(count, width) in self.layouts[selector] {
+                for _ in 0..count {
+                    if j < i + n {
+                        word |= gaps[j] << shift;
+                        j += 1;
+                    }
+                    shift += width;
+                }
+            }
+            builder.append_bits(word, self.word_bits);
+            i += n;
+        }
+    }
+
+    /// reads 64 bits at a time and splits them into words, then moves the iterator
+    /// right after the last word of the list
+    fn read_list(&self, it: &mut BitVectorIterator, out: &mut Vec<u64>) {
+        let list_size = it.take(32) as usize;
+        it.take(self.padding(it.position()));
+
+        let start = it.position();
+        let mut num_words = 0;
+        let mut buf = 0;
+        let mut next_word = || {
+            if num_words * self.word_bits % 64 == 0 {
+                buf = it.take(64);
+            }
+            num_words += 1;
+            let word = buf & (u64::MAX >> (64 - self.word_bits));
+            buf = buf.checked_shr(self.word_bits as u32).unwrap_or(0);
+            word
+        };
+
+        let end = out.len() + list_size;
+        let mut prev_x = 0;
+        let mut gaps = [0; 240];
+        while out.len() < end {
+            let mut word = next_word();
+            let selector = (word & 15) as usize;
+            word >>= 4;
+            let mut n = 0;
+            if selector == self.escape && word == self.escape_value() {
+                gaps[0] = 0;
+                for shift in (0..64).step_by(self.word_bits) {
+                    gaps[0] |= next_word() << shift;
+                }
+                n = 1;
+            } else {
+                for &(count, width) in self.layouts[selector] {
+                    let mask = (1u64 << width) - 1;
+                    for gap in &mut gaps[n..n + count] {
+                        *gap = word & mask;
+                        word >>= width;
+                    }
+                    n += count;
+                }
+            }
+            let n = n.min(end - out.len());
+            push_prefix_sums(out, &mut prev_x, &gaps[..n]);
+        }
+        it.at(start + num_words * self.word_bits);
+    }
+}
+
+pub struct Simple9Code;
+
+impl ListCode for Simple9Code {
+    fn write_list(builder: &mut BitVectorBuilder, list: &[u64]) {
+        SIMPLE9.write_list(builder, list);
+    }
+
+    fn read_list(it: &mut BitVectorIterator, out: &mut Vec<u64>) {
+        SIMPLE9.read_list(it, out);
+    }
+}
+
+pub struct Simple16Code;
+
+impl ListCode for Simple16Code {
+    fn write_list(builder: &mut BitVectorBuilder, list: &[u64]) {
+        SIMPLE16.write_list(builder, list);
+    }
+
+    fn read_list(it: &mut BitVectorIterator, out: &mut Vec<u64>) {
+        SIMPLE16.read_list(it, out);
+    }
+}
+
+pub struct Simple8bCode;
+
+impl ListCode for Simple8bCode {
+    fn write_list(builder: &mut BitVectorBuilder, list: &[u64]) {
+        SIMPLE8B.write_list(builder, list);
+    }
+
+    fn read_list(it: &mut BitVectorIterator, out: &mut Vec<u64>) {
+        SIMPLE8B.read_list(it, out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_utils::{check_lists, random_list};
+    use super::*;
+
+    #[test]
+    fn layouts() {
+        for simple in [&SIMPLE9, &SIMPLE16, &SIMPLE8B] {
+            for layout in simple.layouts.iter().filter(|layout| !layout.is_empty()) {
+                let bits: usize = layout.iter().map(|&(count, width)| count * width).sum();
+                assert!(bits <= simple.word_bits - 4);
+            }
+            assert_eq!(simple.layouts[simple.escape], [(1, simple.word_bits - 4)]);
+        }
+    }
+
+    #[test]
+    fn simple() {
+        let lists = [
+            vec![],
+            vec![0],
+            vec![0; 1000],
+            (1..300).collect(),
+            vec![3, 1 << 20, (1 << 20) + 1, (1 << 27) + 5],
+            random_list(10_000, 0..=3, 1),
+            random_list(10_000, 0..=1000, 2),
+            random_list(1000, 0..=1 << 27, 3),
+        ];
+        let simple9_bits = check_lists::<Simple9Code>(&lists);
+        let simple16_bits = check_lists::<Simple16Code>(&lists);
+        let simple8b_bits = check_lists::<Simple8bCode>(&lists);
+        println!(
+            "simple9: {} bits, simple16: {} bits, simple8b: {} bits",
+            simple9_bits, simple16_bits, simple8b_bits
+        );
+        assert!(simple16_bits <= simple9_bits);
+
+        // only Simple8b has 60-bit slots
+        check_lists::<Simple8bCode>(&[vec![1 << 40, (1 << 59) + (1 << 40)]]);
+    }
+
+    #[test]
+    fn simple_escape() {
+        let lists = [
+            vec![(1 << 28) - 2, (1 << 29) - 3, 1 << 29, (1 << 30) + 7],
+            vec![1, 2, 1 << 60, (1 << 60) + 1, u64::MAX - 1, u64::MAX],
+            vec![0, (1 << 60) - 1, (1 << 61) - 2],
+            vec![u64::MAX],
+            vec![3; 7].into_iter().chain([u64::MAX]).collect(),
+        ];
+        check_lists::<Simple9Code>(&lists);
+        check_lists::<Simple16Code>(&lists);
+        check_lists::<Simple8bCode>(&lists);
+
+        // an escape word and the 64-bit gap
+        let mut builder = BitVectorBuilder::new();
+        Simple16Code::write_list(&mut builder, &[1 << 28]);
+        assert_eq!(builder.num_bits(), 32 + 32 + 64);
+    }
+}
@@ -0,0 +1,249 @@
+use super::bit_vector_iterator::BitVectorIterator;
+use super::elias_fano::EliasFano;
+use super::IntegerCode;
+use crate::introduction::bit_vector::{BitVector, BitVectorBuilder};
+use bincode::{deserialize_from, serialize_into};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::marker::PhantomData;
+
+/// Skip pointers over a sorted list whose gaps are written with an integer code.
+///
+/// The list is cut into blocks of block_size values. For each block, we keep the value
+/// preceding it (0 for the first block) and the bit position of its first gap, so that
+/// a cursor can jump to the block holding the answer of next_geq and decode only this block.
+#[derive(Default, Serialize, Deserialize)]
+pub struct SkipPointers {
+    block_size: usize,
+    num_values: usize,
+    bases: EliasFano,
+    offsets: EliasFano,
+}
+
+impl SkipPointers {
+    pub fn new() -> Self {
+        Self {
+            block_size: 0,
+            num_values: 0,
+            bases: EliasFano::new(),
+            offsets: EliasFano::new(),
+        }
+    }
+
+    /// append the gaps of the sorted list to the builder with the code C,
+    /// and sample the blocks of block_size values
+    pub fn encode<C: IntegerCode<u64>>(
+        &mut self,
+        builder: &mut BitVectorBuilder,
+        list: &[u64],
+        block_size: usize,
+    ) {
+        debug_assert!(block_size > 0);
+        let mut bases = Vec::with_capacity(list.len().div_ceil(block_size));
+        let mut offsets = Vec::with_capacity(bases.capacity());
+        let mut prev_x = 0;
+        for (i, &x) in list.iter().enumerate() {
+            debug_assert!(x >= prev_x);
+            if i % block_size == 0 {
+                bases.push(prev_x);
+                offsets.push(builder.num_bits() as u64);
+            }
+            C::write(builder, x - prev_x);
+            prev_x = x;
+        }
+        self.block_size = block_size;
+        self.num_values = list.len();
+        self.bases.build(&bases);
+        self.offsets.build(&offsets);
+    }
+
+    pub fn len(&self) -> usize {
+        self.num_values
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.num_values == 0
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// number of bits taken by the skip pointers, excluding the list
+    pub fn num_bits(&self) -> usize {
+        self.bases.num_bits() + self.offsets.num_bits()
+    }
+
+    /// cursor over the list written by `encode` with the code C in bv
+    pub fn cursor<'a, C: IntegerCode<u64>>(&'a self, bv: &'a BitVector) -> SkipCursor<'a, C> {
+        let start = if self.is_empty() {
+            0
+        } else {
+            self.offsets.access(0) as usize
+        };
+        SkipCursor {
+            skips: self,
+            it: BitVectorIterator::new(bv, start),
+            i: 0,
+            value: 0,
+            code: PhantomData,
+        }
+    }
+
+    pub fn save<W: Write>(&self, writer: W) {
+        serialize_into(writer, &self).unwrap();
+    }
+
+    pub fn load<R: Read>(&mut self, reader: R) {
+        *self = deserialize_from(reader).unwrap();
+    }
+}
+
+pub struct SkipCursor<'a, C> {
+    skips: &'a SkipPointers,
+    it: BitVectorIterator<'a>,
+    /// number of values decoded so far
+    i: usize,
+    /// last decoded value, or the base of the current block
+    value: u64,
+    code: PhantomData<C>,
+}
+
+impl<C: IntegerCode<u64>> SkipCursor<'_, C> {
+    /// return the position and value of the first value >= x, if any, never moving backwards:
+    /// the answer is at or after the one of the previous call
+    pub fn next_geq(&mut self, x: u64) -> Option<(usize, u64)> {
+        if self.i > 0 && self.value >= x {
+            return Some((self.i - 1, self.value));
+        }
+        // the answer is in the last block whose base is smaller than x
+        let num_blocks = self.skips.bases.len();
+        let block = match self.skips.bases.next_geq(x) {
+            Some((j, _)) => j.saturating_sub(1),
+            None => num_blocks.saturating_sub(1),
+        };
+        let block_start = block * self.skips.block_size;
+        if block_start > self.i {
+            self.it.at(self.skips.offsets.access(block) as usize);
+            self.i = block_start;
+            self.value = self.skips.bases.access(block);
+        }
+        while self.i < self.skips.num_values {
+            self.value += C::read(&mut self.it);
+            self.i += 1;
+            if self.value >= x {
+                return Some((self.i - 1, self.value));
+            }
+        }
+        None
+    }
+}
+
+impl<C: IntegerCode<u64>> Iterator for SkipCursor<'_, C> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.i == self.skips.num_values {
+            return None;
+        }
+        self.value += C::read(&mut self.it);
+        self.i += 1;
+        Some(self.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_utils::random_list;
+    use super::super::{DeltaCode, GammaCode, VByteCode};
+    use super::*;
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+    use std::time::Instant;
+
+    fn check<C: IntegerCode<u64>>(list: &[u64], block_size: usize) {
+        let mut builder = BitVectorBuilder::new();
+        builder.append_bits(0, 8); // the list does not start at position 0
+        let mut skips = SkipPointers::new();
+        skips.encode::<C>(&mut builder, list, block_size);
+        let mut bv = BitVector::new();
+        builder.build(&mut bv);
+
+        assert_eq!(skips.cursor::<C>(&bv).collect::<Vec<_>>(), *list);
+
+        let expected = |x: u64| {
+            let i = list.partition_point(|&v| v < x);
+            list.get(i).map(|&v| (i, v))
+        };
+        // increasing queries on the same cursor
+        let mut rng = SmallRng::seed_from_u64(block_size as u64);
+        let last = list.last().copied().unwrap_or(0);
+        let mut queries: Vec<u64> = (0..200).map(|_| rng.gen_range(0..last + 10)).collect();
+        queries.extend(list.iter().step_by(97).copied());
+        queries.extend([0, last, last + 1]);
+        queries.sort_unstable();
+        let mut cursor = skips.cursor::<C>(&bv);
+        for &x in &queries {
+            assert_eq!(cursor.next_geq(x), expected(x));
+        }
+        // a fresh cursor for each query
+        for &x in &queries {
+            assert_eq!(skips.cursor::<C>(&bv).next_geq(x), expected(x));
+        }
+    }
+
+    #[test]
+    fn skip_pointers() {
+        let lists = [
+            vec![],
+            vec![0],
+            vec![0, 0, 0, 5],
+            (0..1000).collect(),
+            random_list(10_000, 1..100, 1),
+        ];
+        for list in &lists {
+            for block_size in [1, 3, 16, 64, 128, 100_000] {
+                check::<GammaCode>(list, block_size);
+                check::<DeltaCode>(list, block_size);
+                check::<VByteCode>(list, block_size);
+            }
+        }
+    }
+
+    fn bench<C: IntegerCode<u64>>(name: &str, list: &[u64], queries: &[u64]) {
+        for block_size in [16, 32, 64, 128, 256, 1024, list.len()] {
+            let mut builder = BitVectorBuilder::new();
+            let mut skips = SkipPointers::new();
+            skips.encode::<C>(&mut builder, list, block_size);
+            let mut bv = BitVector::new();
+            builder.build(&mut bv);
+
+            let now = Instant::now();
+            let mut checksum = 0;
+            for &x in queries {
+                let mut cursor = skips.cursor::<C>(&bv);
+                checksum += cursor.next_geq(x).map_or(0, |(i, _)| i);
+            }
+            let elapsed = now.elapsed().as_nanos();
+            println!(
+                "{} B={}: {:.2} ns/query, skips take {:.3} bits/int (checksum {})",
+                name,
+                block_size,
+                elapsed as f64 / queries.len() as f64,
+                skips.num_bits() as f64 / list.len() as f64,
+                checksum
+            );
+        }
+    }
+
+    #[test]
+    fn skip_pointers_block_sizes() {
+        let list = random_list(200_000, 1..100, 2);
+        let mut rng = SmallRng::seed_from_u64(3);
+        let last = *list.last().unwrap();
+        let queries: Vec<u64> = (0..2000).map(|_| rng.gen_range(0..=last)).collect();
+        bench::<GammaCode>("gamma", &list, &queries);
+        bench::<DeltaCode>("delta", &list, &queries);
+        bench::<VByteCode>("vbyte", &list, &queries);
+    }
+}
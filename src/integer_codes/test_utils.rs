@@ -0,0 +1,38 @@
+use super::bit_vector_iterator::BitVectorIterator;
+use super::ListCode;
+use crate::introduction::bit_vector::{BitVector, BitVectorBuilder};
+use rand::distributions::uniform::SampleRange;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+/// sorted list of n values whose gaps are drawn from the range
+pub fn random_list<R: SampleRange<u64> + Clone>(n: usize, gaps: R, seed: u64) -> Vec<u64> {
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let mut x = 0;
+    (0..n)
+        .map(|_| {
+            x += rng.gen_range(gaps.clone());
+            x
+        })
+        .collect()
+}
+
+/// write the lists with C, read them back up to the last bit and return the number of bits
+pub fn check_lists<C: ListCode>(lists: &[Vec<u64>]) -> usize {
+    let mut builder = BitVectorBuilder::new();
+    builder.append_bits(0, 5); // lists do not start on a word boundary
+    for list in lists {
+        C::write_list(&mut builder, list);
+    }
+    let mut bv = BitVector::new();
+    builder.build(&mut bv);
+
+    let mut it = BitVectorIterator::new(&bv, 5);
+    for list in lists {
+        let mut decoded = Vec::new();
+        C::read_list(&mut it, &mut decoded);
+        assert_eq!(decoded, *list);
+    }
+    assert_eq!(it.position(), bv.num_bits());
+    bv.num_bits() - 5
+}
@@ -0,0 +1,195 @@
+use super::bit_vector_iterator::BitVectorIterator;
+use super::{gaps, push_prefix_sums, ListCode};
+use crate::introduction::bit_vector::BitVectorBuilder;
+use crate::introduction::util::msb;
+
+/// how to decode the group of four values described by a control byte, whose i-th pair of
+/// bits is the number of bytes of the i-th value minus 1: the data bytes of the group are
+/// read as one little-endian block and the i-th value is (block >> shifts[i]) & masks[i],
+/// a scalar version of the shuffle masks of the SIMD decoders
+#[derive(Clone, Copy)]
+struct GroupDecoder {
+    shifts: [u32; 4],
+    masks: [u64; 4],
+    num_bytes: usize,
+}
+
+/// DECODERS[c] decodes the group whose control byte is c
+const DECODERS: [GroupDecoder; 256] = {
+    let mut decoders = [GroupDecoder {
+        shifts: [0; 4],
+        masks: [0; 4],
+        num_bytes: 0,
+    }; 256];
+    let mut c = 0;
+    while c < 256 {
+        let mut i = 0;
+        while i < 4 {
+            let len = ((c >> (2 * i)) & 3) + 1;
+            decoders[c].shifts[i] = 8 * decoders[c].num_bytes as u32;
+            decoders[c].masks[i] = u64::MAX >> (64 - 8 * len);
+            decoders[c].num_bytes += len;
+            i += 1;
+        }
+        c += 1;
+    }
+    decoders
+};
+
+/// number of bytes of x < 2^32, at least 1
+fn num_bytes(x: u64) -> usize {
+    assert!(x >> 32 == 0, "gaps must fit in 32 bits");
+    if x == 0 {
+        1
+    } else {
+        msb(x) / 8 + 1
+    }
+}
+
+/// control byte of a group of at most four values, the missing ones taking 1 byte
+fn control_byte(group: &[u64]) -> u64 {
+    group
+        .iter()
+        .enumerate()
+        .map(|(i, &x)| ((num_bytes(x) - 1) as u64) << (2 * i))
+        .sum()
+}
+
+/// append the bytes of the values of a group, the missing ones being written as zeros
+fn write_group_data(builder: &mut BitVectorBuilder, group: &[u64]) {
+    for i in 0..4 {
+        let x = group.get(i).copied().unwrap_or(0);
+        builder.append_bits(x, 8 * num_bytes(x));
+    }
+}
+
+/// decode the four values of a group whose control byte is c, reading all their bytes at once
+fn read_group_data(it: &mut BitVectorIterator, c: usize, out: &mut [u64; 4]) {
+    let decoder = &DECODERS[c];
+    let bits = 8 * decoder.num_bytes;
+    let low = it.take(bits.min(64)) as u128;
+    let block = low | (it.take(bits.saturating_sub(64)) as u128) << 64;
+    for ((v, &shift), &mask) in out.iter_mut().zip(&decoder.shifts).zip(&decoder.masks) {
+        *v = (block >> shift) as u64 & mask;
+    }
+}
+
+/// Group Varint (Dean, 2009): the gaps are coded in groups of four, each group starting with
+/// a control byte giving the number of bytes of its values, followed by their bytes.
+/// All gaps must fit in 32 bits.
+pub struct GroupVarintCode;
+
+impl ListCode for GroupVarintCode {
+    fn write_list(builder: &mut BitVectorBuilder, list: &[u64]) {
+        builder.append_bits(list.len() as u64, 32);
+        for group in gaps(list).chunks(4) {
+            builder.append_bits(control_byte(group), 8);
+            write_group_data(builder, group);
+        }
+    }
+
+    fn read_list(it: &mut BitVectorIterator, out: &mut Vec<u64>) {
+        let list_size = it.take(32) as usize;
+        let mut group = [0; 4];
+        let mut prev_x = 0;
+        let mut remaining = list_size;
+        while remaining > 0 {
+            let c = it.take(8) as usize;
+            read_group_data(it, c, &mut group);
+            let n = remaining.min(4);
+            push_prefix_sums(out, &mut prev_x, &group[..n]);
+            remaining -= n;
+        }
+    }
+}
+
+/// Stream VByte (Lemire et al., 2018): the same groups as Group Varint, but all the control
+/// bytes of the list are written first, followed by all the data bytes.
+/// All gaps must fit in 32 bits.
+pub struct StreamVByteCode;
+
+impl ListCode for StreamVByteCode {
+    fn write_list(builder: &mut BitVectorBuilder, list: &[u64]) {
+        builder.append_bits(list.len() as u64, 32);
+        let gaps = gaps(list);
+        for group in gaps.chunks(4) {
+            builder.append_bits(control_byte(group), 8);
+        }
+        for group in gaps.chunks(4) {
+            write_group_data(builder, group);
+        }
+    }
+
+    fn read_list(it: &mut BitVectorIterator, out: &mut Vec<u64>) {
+        let list_size = it.take(32) as usize;
+        let controls: Vec<usize> = (0..list_size.div_ceil(4))
+            .map(|_| it.take(8) as usize)
+            .collect();
+        let mut group = [0; 4];
+        let mut prev_x = 0;
+        let mut remaining = list_size;
+        for c in controls {
+            read_group_data(it, c, &mut group);
+            let n = remaining.min(4);
+            push_prefix_sums(out, &mut prev_x, &group[..n]);
+            remaining -= n;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_utils::check_lists;
+    use super::*;
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn group_varint_and_stream_vbyte() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let mut x = 0;
+        let random: Vec<u64> = (0..10_001)
+            .map(|_| {
+                let b = rng.gen_range(0..25);
+                x += rng.gen_range(0..1u64 << b);
+                x
+            })
+            .collect();
+        let lists = [
+            vec![],
+            vec![0],
+            vec![1, 300, 70_000, 1 << 24],
+            vec![u32::MAX as u64, u32::MAX as u64 + 255],
+            (0..10).collect(),
+            random,
+        ];
+        let group_varint_bits = check_lists::<GroupVarintCode>(&lists);
+        let stream_vbyte_bits = check_lists::<StreamVByteCode>(&lists);
+        assert_eq!(group_varint_bits, stream_vbyte_bits);
+
+        // 1 control byte and 1 + 2 + 3 + 4 data bytes
+        let mut builder = BitVectorBuilder::new();
+        GroupVarintCode::write_list(&mut builder, &[1, 257, 65_793, 16_843_009]);
+        assert_eq!(builder.num_bits(), 32 + 8 * 11);
+    }
+
+    #[test]
+    #[should_panic(expected = "gaps must fit in 32 bits")]
+    fn group_varint_large_gap() {
+        let mut builder = BitVectorBuilder::new();
+        GroupVarintCode::write_list(&mut builder, &[1, (1 << 32) + 1]);
+    }
+
+    #[test]
+    fn decoders_table() {
+        assert_eq!(DECODERS[0].shifts, [0, 8, 16, 24]);
+        assert_eq!(DECODERS[0].masks, [255; 4]);
+        assert_eq!(DECODERS[0].num_bytes, 4);
+        let decoder = &DECODERS[0b11_10_01_00];
+        assert_eq!(decoder.shifts, [0, 8, 24, 48]);
+        assert_eq!(decoder.masks, [0xFF, 0xFFFF, 0xFF_FFFF, 0xFFFF_FFFF]);
+        assert_eq!(decoder.num_bytes, 10);
+        assert_eq!(DECODERS[255].shifts, [0, 32, 64, 96]);
+        assert_eq!(DECODERS[255].num_bytes, 16);
+    }
+}
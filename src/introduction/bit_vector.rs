@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 use std::io::{Read, Write};
 use std::mem::swap;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct BitVector {
     num_bits: usize,
     bits: Vec<u64>,
@@ -26,6 +26,16 @@ impl BitVector {
         self.bits.len()
     }
 
+    /// the underlying 64-bit words, the last one being padded with zeros
+    pub fn words(&self) -> &[u64] {
+        &self.bits
+    }
+
+    pub fn get_bit(&self, pos: usize) -> bool {
+        debug_assert!(pos < self.num_bits);
+        (self.bits[pos / 64] >> (pos % 64)) & 1 == 1
+    }
+
     pub fn get_bits(&self, pos: usize, len: usize) -> u64 {
         debug_assert!(pos + len <= self.num_bits);
         debug_assert!(len <= 64);
@@ -36,7 +46,7 @@ impl BitVector {
 
         let block = pos / 64;
         let shift = pos % 64;
-        let mask = (1u64 << len).wrapping_sub(1);
+        let mask = u64::MAX >> (64 - len);
 
         if shift + len <= 64 {
             (self.bits[block] >> shift) & mask
@@ -71,6 +81,7 @@ impl BitVector {
     }
 }
 
+#[derive(Default)]
 pub struct BitVectorBuilder {
     num_bits: usize,
     bits: Vec<u64>,
@@ -110,7 +121,7 @@ impl BitVectorBuilder {
             return;
         }
 
-        let mask = (1u64 << len).wrapping_sub(1);
+        let mask = u64::MAX >> (64 - len);
         let word = pos / 64;
         let pos_in_word = pos % 64;
 
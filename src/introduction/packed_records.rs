@@ -25,8 +25,7 @@ impl Record {
 #[test]
 fn packed_records() {
     let n = 10000;
-    let mut records: Vec<Record> = Vec::new();
-    records.reserve(n);
+    let mut records: Vec<Record> = Vec::with_capacity(n);
 
     let mut rng = SmallRng::seed_from_u64(3);
     for _ in 0..n {
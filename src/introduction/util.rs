@@ -2,7 +2,7 @@ use num_traits::int::PrimInt;
 
 /// number of 64-bit words for num_bits
 pub fn num_64bit_words_for(num_bits: usize) -> usize {
-    (num_bits + 64 - 1) / 64
+    num_bits.div_ceil(64)
 }
 
 /// position of the most significant bit (msb)
@@ -15,3 +15,71 @@ pub fn msb<T: PrimInt>(x: T) -> usize {
 pub fn lsb<T: PrimInt>(x: T) -> usize {
     x.trailing_zeros() as usize
 }
+
+const ONES_STEP_4: u64 = 0x1111_1111_1111_1111;
+const ONES_STEP_8: u64 = 0x0101_0101_0101_0101;
+const MSBS_STEP_8: u64 = 0x80 * ONES_STEP_8;
+
+/// SELECT_IN_BYTE[b | (k << 8)] is the position of the k-th one in the byte b
+const SELECT_IN_BYTE: [u8; 2048] = {
+    let mut table = [0u8; 2048];
+    let mut b = 0;
+    while b < 256 {
+        let mut k = 0;
+        let mut pos = 0;
+        while pos < 8 {
+            if (b >> pos) & 1 == 1 {
+                table[b | (k << 8)] = pos as u8;
+                k += 1;
+            }
+            pos += 1;
+        }
+        b += 1;
+    }
+    table
+};
+
+/// number of ones in each byte of x
+fn byte_counts(x: u64) -> u64 {
+    let x = x - ((x & (0xa * ONES_STEP_4)) >> 1);
+    let x = (x & (3 * ONES_STEP_4)) + ((x >> 2) & (3 * ONES_STEP_4));
+    (x + (x >> 4)) & (0x0f * ONES_STEP_8)
+}
+
+/// position of the k-th one (starting from 0) in x, assuming k < popcount(x)
+///
+/// only uses broadword operations and a small table, so it stays portable
+pub fn select_in_word(x: u64, k: usize) -> usize {
+    debug_assert!(k < x.count_ones() as usize);
+    let byte_sums = byte_counts(x).wrapping_mul(ONES_STEP_8);
+    let k_step_8 = k as u64 * ONES_STEP_8;
+    let geq_k_step_8 = ((k_step_8 | MSBS_STEP_8) - byte_sums) & MSBS_STEP_8;
+    let place = geq_k_step_8.count_ones() as usize * 8;
+    let byte_rank = k - (((byte_sums << 8) >> place) & 0xFF) as usize;
+    place + SELECT_IN_BYTE[((x >> place) & 0xFF) as usize | (byte_rank << 8)] as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn select_in_word_naive() {
+        let mut rng = SmallRng::seed_from_u64(5);
+        let mut words = vec![1, 1 << 63, u64::MAX, 0x8000_0000_0000_0001];
+        for _ in 0..1000 {
+            words.push(rng.gen::<u64>() & rng.gen::<u64>());
+        }
+        for x in words {
+            let mut k = 0;
+            for pos in 0..64 {
+                if (x >> pos) & 1 == 1 {
+                    assert_eq!(select_in_word(x, k), pos);
+                    k += 1;
+                }
+            }
+        }
+    }
+}
@@ -1,2 +1,6 @@
+// `x % n == 0` reads better than `x.is_multiple_of(n)` next to the other bit arithmetic
+#![allow(clippy::manual_is_multiple_of)]
+
 pub mod integer_codes;
 pub mod introduction;
+pub mod succinct;
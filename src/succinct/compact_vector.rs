@@ -0,0 +1,202 @@
+use crate::introduction::bit_vector::{BitVector, BitVectorBuilder};
+use crate::introduction::util::msb;
+use bincode::{deserialize_from, serialize_into};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+
+/// number of bits needed to store integers up to max, at least 1
+pub fn width_for(max: u64) -> usize {
+    msb(max | 1) + 1
+}
+
+/// builder of a CompactVector: integers of width bits are appended with `push`,
+/// and can be overwritten with `set` until the vector is built
+pub struct CompactVectorBuilder {
+    width: usize,
+    len: usize,
+    bits: BitVectorBuilder,
+}
+
+impl CompactVectorBuilder {
+    pub fn new(width: usize) -> Self {
+        debug_assert!(width > 0 && width <= 64);
+        Self {
+            width,
+            len: 0,
+            bits: BitVectorBuilder::new(),
+        }
+    }
+
+    /// builder of n zeros
+    pub fn with_len(width: usize, n: usize) -> Self {
+        let mut builder = Self::new(width);
+        builder.bits.resize(n * width);
+        builder.len = n;
+        builder
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn push(&mut self, x: u64) {
+        self.bits.append_bits(x, self.width);
+        self.len += 1;
+    }
+
+    pub fn set(&mut self, i: usize, x: u64) {
+        debug_assert!(i < self.len);
+        self.bits.set_bits(i * self.width, x, self.width);
+    }
+
+    pub fn build(&mut self, cv: &mut CompactVector) {
+        cv.width = self.width;
+        cv.len = self.len;
+        self.bits.build(&mut cv.bits);
+        self.len = 0;
+    }
+}
+
+/// vector of integers stored on exactly width bits each, with O(1) access
+#[derive(Default, Serialize, Deserialize)]
+pub struct CompactVector {
+    width: usize,
+    len: usize,
+    bits: BitVector,
+}
+
+impl CompactVector {
+    pub fn new() -> Self {
+        Self {
+            width: 0,
+            len: 0,
+            bits: BitVector::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// total number of bits of the packed integers
+    pub fn num_bits(&self) -> usize {
+        self.bits.num_bits()
+    }
+
+    pub fn get(&self, i: usize) -> u64 {
+        debug_assert!(i < self.len);
+        self.bits.get_bits(i * self.width, self.width)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        (0..self.len).map(|i| self.get(i))
+    }
+
+    pub fn save<W: Write>(&self, writer: W) {
+        serialize_into(writer, &self).unwrap();
+    }
+
+    pub fn load<R: Read>(&mut self, reader: R) {
+        *self = deserialize_from(reader).unwrap();
+    }
+}
+
+/// collect the integers with the width of the largest one
+impl FromIterator<u64> for CompactVector {
+    fn from_iter<I: IntoIterator<Item = u64>>(iter: I) -> Self {
+        let values: Vec<u64> = iter.into_iter().collect();
+        let max = values.iter().copied().max().unwrap_or(0);
+        let mut builder = CompactVectorBuilder::new(width_for(max));
+        for x in values {
+            builder.push(x);
+        }
+        let mut cv = CompactVector::new();
+        builder.build(&mut cv);
+        cv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn compact_vector() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        for width in [1, 3, 7, 13, 32, 63, 64] {
+            let max = u64::MAX >> (64 - width);
+            let values: Vec<u64> = (0..10_000).map(|_| rng.gen_range(0..=max)).collect();
+
+            let mut builder = CompactVectorBuilder::new(width);
+            for &x in &values {
+                builder.push(x);
+            }
+            let mut cv = CompactVector::new();
+            builder.build(&mut cv);
+            assert_eq!(cv.len(), values.len());
+            assert_eq!(cv.num_bits(), width * values.len());
+            for (i, &x) in values.iter().enumerate() {
+                assert_eq!(cv.get(i), x);
+            }
+            assert_eq!(cv.iter().collect::<Vec<_>>(), values);
+        }
+    }
+
+    #[test]
+    fn compact_vector_set() {
+        let n = 1000;
+        let mut builder = CompactVectorBuilder::with_len(11, n);
+        for i in (0..n).rev() {
+            builder.set(i, (i * 7 % 2048) as u64);
+        }
+        builder.set(5, 2047);
+        builder.set(5, 3);
+        let mut cv = CompactVector::new();
+        builder.build(&mut cv);
+        for i in 0..n {
+            let expected = if i == 5 { 3 } else { (i * 7 % 2048) as u64 };
+            assert_eq!(cv.get(i), expected);
+        }
+    }
+
+    #[test]
+    fn compact_vector_from_iter() {
+        let cv: CompactVector = (0..1000u64).map(|i| i * i).collect();
+        assert_eq!(cv.width(), width_for(999 * 999));
+        assert_eq!(cv.width(), 20);
+        assert!(cv.iter().enumerate().all(|(i, x)| x == (i * i) as u64));
+
+        let empty: CompactVector = std::iter::empty().collect();
+        assert!(empty.is_empty());
+        assert_eq!(width_for(0), 1);
+        assert_eq!(width_for(u64::MAX), 64);
+
+        let mut buf = Vec::new();
+        cv.save(&mut buf);
+        let mut loaded = CompactVector::new();
+        loaded.load(&buf[..]);
+        assert_eq!(loaded.width(), cv.width());
+        assert_eq!(
+            loaded.iter().collect::<Vec<_>>(),
+            cv.iter().collect::<Vec<_>>()
+        );
+    }
+}
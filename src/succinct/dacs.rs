@@ -0,0 +1,256 @@
+use super::compact_vector::{width_for, CompactVector, CompactVectorBuilder};
+use super::rank9::Rank9;
+use crate::introduction::bit_vector::{BitVector, BitVectorBuilder};
+use bincode::{deserialize_from, serialize_into};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+
+#[derive(Default, Serialize, Deserialize)]
+struct Level {
+    chunks: CompactVector,
+    /// bit i is set if the i-th value of the level continues in the next level
+    continuation: BitVector,
+    rank: Rank9,
+}
+
+/// Directly Addressable Codes (Brisaboa, Ladra and Navarro, 2013).
+///
+/// Each value is split into chunks of widths w_0, w_1, ..., from its low bits. The level l
+/// stores the l-th chunk of all the values that have one, with a continuation bit telling
+/// whether the value has more chunks. The position of a value in the next level is the rank
+/// of its continuation bit, so any value is accessed without decoding the others.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Dacs {
+    num_values: usize,
+    widths: Vec<usize>,
+    levels: Vec<Level>,
+}
+
+/// number of bits taken by a level of n chunks of width w, with or without continuation bits
+fn level_cost(n: usize, w: usize, has_next: bool) -> usize {
+    if has_next {
+        n * w + n + n / 4 // the continuation bits and their Rank9 directory
+    } else {
+        n * w
+    }
+}
+
+/// chunk widths minimizing the total space of the values, by dynamic programming
+/// over the bit positions where the levels start
+pub fn optimal_widths(values: &[u64]) -> Vec<usize> {
+    let max_width = width_for(values.iter().copied().max().unwrap_or(0));
+    // num_longer[s] is the number of values stored in a level starting at bit s
+    let mut num_longer = vec![0; max_width + 1];
+    for &x in values {
+        for count in num_longer.iter_mut().take(width_for(x)) {
+            *count += 1;
+        }
+    }
+
+    // cost[s] and next[s] are the best cost and the start of the next level
+    // for the bits from s onwards
+    let mut cost = vec![0; max_width + 1];
+    let mut next = vec![max_width; max_width + 1];
+    for s in (0..max_width).rev() {
+        let n = num_longer[s];
+        (cost[s], next[s]) = (s + 1..=max_width)
+            .map(|e| (level_cost(n, e - s, e < max_width) + cost[e], e))
+            .min()
+            .unwrap();
+    }
+
+    let mut widths = Vec::new();
+    let mut s = 0;
+    while s < max_width {
+        widths.push(next[s] - s);
+        s = next[s];
+    }
+    widths
+}
+
+impl Dacs {
+    pub fn new() -> Self {
+        Self {
+            num_values: 0,
+            widths: Vec::new(),
+            levels: Vec::new(),
+        }
+    }
+
+    /// build with the optimal chunk widths for the values
+    pub fn build(&mut self, values: &[u64]) {
+        self.build_with_widths(values, &optimal_widths(values));
+    }
+
+    /// build with the given chunk widths, cut or extended by a last level
+    /// so that the levels cover exactly the width of the largest value
+    pub fn build_with_widths(&mut self, values: &[u64], widths: &[usize]) {
+        let max_width = width_for(values.iter().copied().max().unwrap_or(0));
+        let mut covered = 0;
+        let mut level_widths = Vec::new();
+        for &w in widths.iter().take_while(|&&w| w > 0) {
+            if covered == max_width {
+                break;
+            }
+            level_widths.push(w.min(max_width - covered));
+            covered += level_widths.last().unwrap();
+        }
+        if covered < max_width {
+            level_widths.push(max_width - covered);
+        }
+        let widths = level_widths;
+
+        self.num_values = values.len();
+        self.levels.clear();
+        let mut remaining: Vec<u64> = values.to_vec();
+        let mut shift = 0;
+        for (l, &w) in widths.iter().enumerate() {
+            let has_next = l + 1 < widths.len();
+            let mut chunks = CompactVectorBuilder::new(w);
+            let mut continuation = BitVectorBuilder::new();
+            let mut next = Vec::new();
+            for &x in &remaining {
+                chunks.push((x >> shift) & (u64::MAX >> (64 - w)));
+                if has_next {
+                    let continues = width_for(x) > shift + w;
+                    continuation.append_bits(continues as u64, 1);
+                    if continues {
+                        next.push(x);
+                    }
+                }
+            }
+            let mut level = Level::default();
+            chunks.build(&mut level.chunks);
+            continuation.build(&mut level.continuation);
+            level.rank.build(&level.continuation);
+            self.levels.push(level);
+            remaining = next;
+            shift += w;
+        }
+        self.widths = widths;
+    }
+
+    pub fn len(&self) -> usize {
+        self.num_values
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.num_values == 0
+    }
+
+    /// chunk width of each level
+    pub fn widths(&self) -> &[usize] {
+        &self.widths
+    }
+
+    /// total number of bits, including the rank directories
+    pub fn num_bits(&self) -> usize {
+        self.levels
+            .iter()
+            .map(|level| {
+                level.chunks.num_bits() + level.continuation.num_bits() + level.rank.num_bits()
+            })
+            .sum()
+    }
+
+    /// return the i-th value
+    pub fn access(&self, i: usize) -> u64 {
+        debug_assert!(i < self.num_values);
+        let mut i = i;
+        let mut x = 0;
+        let mut shift = 0;
+        for (level, &w) in self.levels.iter().zip(&self.widths) {
+            x |= level.chunks.get(i) << shift;
+            if level.continuation.num_bits() == 0 || !level.continuation.get_bit(i) {
+                break;
+            }
+            i = level.rank.rank1(&level.continuation, i);
+            shift += w;
+        }
+        x
+    }
+
+    pub fn save<W: Write>(&self, writer: W) {
+        serialize_into(writer, &self).unwrap();
+    }
+
+    pub fn load<R: Read>(&mut self, reader: R) {
+        *self = deserialize_from(reader).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    /// mostly small values, with a long tail
+    fn skewed_values(n: usize, seed: u64) -> Vec<u64> {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        (0..n)
+            .map(|_| {
+                let b = (rng.gen::<f64>().powi(4) * 40.0) as u32;
+                rng.gen_range(0..1u64 << b)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dacs() {
+        let lists = [
+            vec![],
+            vec![0],
+            vec![0; 100],
+            vec![u64::MAX, 0, 1 << 63, 5],
+            (0..1000).collect(),
+            skewed_values(100_000, 1),
+        ];
+        for values in &lists {
+            let mut dacs = Dacs::new();
+            dacs.build(values);
+            assert_eq!(dacs.len(), values.len());
+            for (i, &x) in values.iter().enumerate() {
+                assert_eq!(dacs.access(i), x);
+            }
+            for widths in [vec![1], vec![4, 4], vec![8], vec![3, 60, 7]] {
+                dacs.build_with_widths(values, &widths);
+                for (i, &x) in values.iter().enumerate() {
+                    assert_eq!(dacs.access(i), x);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dacs_optimal_widths() {
+        let values = skewed_values(100_000, 2);
+        let mut dacs = Dacs::new();
+        dacs.build(&values);
+        let optimal_bits = dacs.num_bits();
+        println!(
+            "optimal widths {:?}: {:.3} bits/int",
+            dacs.widths(),
+            optimal_bits as f64 / values.len() as f64
+        );
+        for w in [2, 4, 8, 16, 40] {
+            dacs.build_with_widths(&values, &[w; 40]);
+            println!(
+                "fixed width {}: {:.3} bits/int",
+                w,
+                dacs.num_bits() as f64 / values.len() as f64
+            );
+            assert!(optimal_bits <= dacs.num_bits());
+        }
+
+        let mut buf = Vec::new();
+        dacs.build(&values);
+        dacs.save(&mut buf);
+        let mut loaded = Dacs::new();
+        loaded.load(&buf[..]);
+        assert_eq!(loaded.widths(), dacs.widths());
+        for (i, &x) in values.iter().enumerate() {
+            assert_eq!(loaded.access(i), x);
+        }
+    }
+}
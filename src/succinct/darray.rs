@@ -0,0 +1,222 @@
+use crate::introduction::bit_vector::BitVector;
+use crate::introduction::util::{lsb, select_in_word};
+use bincode::{deserialize_from, serialize_into};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+
+const BLOCK_SIZE: usize = 1024;
+const SUBBLOCK_SIZE: usize = 32;
+const MAX_IN_BLOCK_DISTANCE: usize = 1 << 16;
+
+/// DArray select directory (Okanohara and Sadakane, 2007) over a BitVector.
+///
+/// The positions are grouped in blocks of 1024. Dense blocks store their first position
+/// and a 16-bit offset every 32 positions, from which the query scans a few words;
+/// sparse blocks, spanning at least 2^16 bits, store all their positions explicitly.
+/// With ONES = false, the directory answers select queries over the zeros instead.
+#[derive(Default, Serialize, Deserialize)]
+pub struct DArray<const ONES: bool> {
+    num_positions: usize,
+    block_inventory: Vec<i64>,
+    subblock_inventory: Vec<u16>,
+    overflow_positions: Vec<u64>,
+}
+
+pub type DArray1 = DArray<true>;
+pub type DArray0 = DArray<false>;
+
+impl<const ONES: bool> DArray<ONES> {
+    pub fn new() -> Self {
+        Self {
+            num_positions: 0,
+            block_inventory: Vec::new(),
+            subblock_inventory: Vec::new(),
+            overflow_positions: Vec::new(),
+        }
+    }
+
+    fn get_word(bv: &BitVector, i: usize) -> u64 {
+        let word = bv.words()[i];
+        if ONES {
+            word
+        } else {
+            !word
+        }
+    }
+
+    pub fn build(&mut self, bv: &BitVector) {
+        *self = Self::new();
+        let num_words = bv.num_64bit_words();
+        let mut positions = Vec::with_capacity(BLOCK_SIZE);
+        for i in 0..num_words {
+            let mut word = Self::get_word(bv, i);
+            let bits_in_last_word = bv.num_bits() % 64;
+            if i + 1 == num_words && bits_in_last_word > 0 {
+                word &= (1u64 << bits_in_last_word) - 1;
+            }
+            while word != 0 {
+                positions.push(64 * i + lsb(word));
+                if positions.len() == BLOCK_SIZE {
+                    self.flush_block(&positions);
+                    positions.clear();
+                }
+                word &= word - 1;
+            }
+        }
+        if !positions.is_empty() {
+            self.flush_block(&positions);
+        }
+    }
+
+    fn flush_block(&mut self, positions: &[usize]) {
+        let first = positions[0];
+        let last = positions[positions.len() - 1];
+        if last - first < MAX_IN_BLOCK_DISTANCE {
+            self.block_inventory.push(first as i64);
+            for &pos in positions.iter().step_by(SUBBLOCK_SIZE) {
+                self.subblock_inventory.push((pos - first) as u16);
+            }
+        } else {
+            self.block_inventory
+                .push(-(self.overflow_positions.len() as i64) - 1);
+            self.overflow_positions
+                .extend(positions.iter().map(|&pos| pos as u64));
+            for _ in positions.iter().step_by(SUBBLOCK_SIZE) {
+                self.subblock_inventory.push(u16::MAX);
+            }
+        }
+        self.num_positions += positions.len();
+    }
+
+    /// number of positions that can be selected
+    pub fn num_positions(&self) -> usize {
+        self.num_positions
+    }
+
+    /// number of bits taken by the directory, excluding the indexed vector
+    pub fn num_bits(&self) -> usize {
+        64 * self.block_inventory.len()
+            + 16 * self.subblock_inventory.len()
+            + 64 * self.overflow_positions.len()
+    }
+
+    /// position of the k-th one (or zero), starting from 0
+    pub fn select(&self, bv: &BitVector, k: usize) -> usize {
+        debug_assert!(k < self.num_positions);
+        let block = k / BLOCK_SIZE;
+        let block_pos = self.block_inventory[block];
+        if block_pos < 0 {
+            let overflow_pos = (-block_pos - 1) as usize;
+            return self.overflow_positions[overflow_pos + k % BLOCK_SIZE] as usize;
+        }
+
+        let subblock = k / SUBBLOCK_SIZE;
+        let start_pos = block_pos as usize + self.subblock_inventory[subblock] as usize;
+        let mut remainder = k % SUBBLOCK_SIZE;
+        if remainder == 0 {
+            return start_pos;
+        }
+
+        let mut i = start_pos / 64;
+        let mut word = Self::get_word(bv, i) & (u64::MAX << (start_pos % 64));
+        loop {
+            let popcount = word.count_ones() as usize;
+            if remainder < popcount {
+                break;
+            }
+            remainder -= popcount;
+            i += 1;
+            word = Self::get_word(bv, i);
+        }
+        64 * i + select_in_word(word, remainder)
+    }
+
+    pub fn save<W: Write>(&self, writer: W) {
+        serialize_into(writer, &self).unwrap();
+    }
+
+    pub fn load<R: Read>(&mut self, reader: R) {
+        *self = deserialize_from(reader).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::introduction::bit_vector::BitVectorBuilder;
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    fn bit_vector_from<F: FnMut(usize) -> bool>(num_bits: usize, mut bit: F) -> BitVector {
+        let mut builder = BitVectorBuilder::new();
+        for pos in 0..num_bits {
+            builder.append_bits(bit(pos) as u64, 1);
+        }
+        let mut bv = BitVector::new();
+        builder.build(&mut bv);
+        bv
+    }
+
+    fn check_against_scan(bv: &BitVector) {
+        let mut select1 = DArray1::new();
+        let mut select0 = DArray0::new();
+        select1.build(bv);
+        select0.build(bv);
+
+        let (mut num_ones, mut num_zeros) = (0, 0);
+        for pos in 0..bv.num_bits() {
+            if bv.get_bit(pos) {
+                assert_eq!(select1.select(bv, num_ones), pos);
+                num_ones += 1;
+            } else {
+                assert_eq!(select0.select(bv, num_zeros), pos);
+                num_zeros += 1;
+            }
+        }
+        assert_eq!(select1.num_positions(), num_ones);
+        assert_eq!(select0.num_positions(), num_zeros);
+    }
+
+    #[test]
+    fn darray_random() {
+        for density in [0.001, 0.01, 0.1, 0.5, 0.9, 0.99, 0.999] {
+            let mut rng = SmallRng::seed_from_u64(17);
+            let bv = bit_vector_from(300_000, |_| rng.gen_bool(density));
+            check_against_scan(&bv);
+        }
+    }
+
+    #[test]
+    fn darray_skewed() {
+        // dense runs separated by long gaps, so that both block kinds are used
+        let bv = bit_vector_from(1_000_000, |pos| pos % 200_000 < 3000);
+        check_against_scan(&bv);
+        let bv = bit_vector_from(1_000_000, |pos| pos < 1000 || pos == 999_999);
+        check_against_scan(&bv);
+        let bv = bit_vector_from(100_000, |_| true);
+        check_against_scan(&bv);
+        let bv = bit_vector_from(0, |_| true);
+        check_against_scan(&bv);
+    }
+
+    #[test]
+    fn darray_save_load() {
+        let mut rng = SmallRng::seed_from_u64(9);
+        let bv = bit_vector_from(50_000, |_| rng.gen_bool(0.3));
+        let mut select = DArray1::new();
+        select.build(&bv);
+        println!(
+            "darray uses {} bits for {} bits",
+            select.num_bits(),
+            bv.num_bits()
+        );
+
+        let mut buf = Vec::new();
+        select.save(&mut buf);
+        let mut loaded = DArray1::new();
+        loaded.load(&buf[..]);
+        for k in 0..select.num_positions() {
+            assert_eq!(loaded.select(&bv, k), select.select(&bv, k));
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod compact_vector;
+pub mod dacs;
+pub mod darray;
+pub mod rank9;